@@ -1,5 +1,18 @@
 set -euxo pipefail
 
+# Generated sources are hashed by reproducible builds: a second run, rendering on a single thread,
+# must give the same bytes
+check_reproducible() {
+    mkdir again
+    (cd again && RAYON_NUM_THREADS=1 RUST_BACKTRACE=1 svd2rust "$@")
+    for f in lib.rs device.x build.rs; do
+        if [ -f $f ]; then
+            cmp $f again/$f
+        fi
+    done
+    rm -r again
+}
+
 test_svd() {
     (
         cd $td &&
@@ -10,6 +23,7 @@ test_svd() {
     # NOTE we care about errors in svd2rust, but not about errors / warnings in rustfmt
     pushd $td
     RUST_BACKTRACE=1 svd2rust $strict $const_generic $derive_more -i ${1}.svd
+    check_reproducible $strict $const_generic $derive_more -i ../${1}.svd
 
     mv lib.rs src/lib.rs
 
@@ -24,6 +38,7 @@ test_svd_for_target() {
     # NOTE we care about errors in svd2rust, but not about errors / warnings in rustfmt
     pushd $td
     RUST_BACKTRACE=1 svd2rust --target $1 -i input.svd
+    check_reproducible --target $1 -i ../input.svd
 
     mv lib.rs src/lib.rs
 
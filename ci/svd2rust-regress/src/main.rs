@@ -46,7 +46,7 @@ struct Opt {
     mfgr: Option<String>,
 
     /// Filter by architecture, case sensitive, may be combined with other filters
-    /// Options are: "CortexM", "RiscV", "Msp430", "Mips" and "XtensaLX"
+    /// Options are: "Avr", "CortexM", "RiscV", "Msp430", "Mips" and "XtensaLX"
     #[structopt(
         short = "a",
         long = "architecture",
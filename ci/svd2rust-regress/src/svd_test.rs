@@ -11,7 +11,8 @@ const CRATES_MSP430_NIGHTLY: &[&str] = &["msp430-atomic = \"0.1.2\""];
 const CRATES_CORTEX_M: &[&str] = &["cortex-m = \"0.7.6\"", "cortex-m-rt = \"0.6.13\""];
 const CRATES_RISCV: &[&str] = &["riscv = \"0.9.0\"", "riscv-rt = \"0.9.0\""];
 const CRATES_XTENSALX: &[&str] = &["xtensa-lx-rt = \"0.9.0\"", "xtensa-lx = \"0.6.0\""];
-const CRATES_MIPS: &[&str] = &["mips-mcu = \"0.1.0\""];
+const CRATES_AVR: &[&str] = &[];
+const CRATES_MIPS: &[&str] = &["mips-mcu = \"0.3.0\"", "mips-rt = \"0.3.0\""];
 const PROFILE_ALL: &[&str] = &["[profile.dev]", "incremental = false"];
 const FEATURES_ALL: &[&str] = &["[features]"];
 const FEATURES_XTENSALX: &[&str] = &["default = [\"xtensa-lx/esp32\", \"xtensa-lx-rt/esp32\"]"];
@@ -130,6 +131,7 @@ pub fn test(
     let crates = CRATES_ALL
         .iter()
         .chain(match &t.arch {
+            Avr => CRATES_AVR.iter(),
             CortexM => CRATES_CORTEX_M.iter(),
             RiscV => CRATES_RISCV.iter(),
             Mips => CRATES_MIPS.iter(),
@@ -172,6 +174,7 @@ pub fn test(
     let lib_rs_file = path_helper_base(&chip_dir, &["src", "lib.rs"]);
     let svd2rust_err_file = path_helper_base(&chip_dir, &["svd2rust.err.log"]);
     let target = match t.arch {
+        Avr => "avr",
         CortexM => "cortex-m",
         Msp430 => "msp430",
         Mips => "mips",
@@ -200,7 +203,7 @@ pub fn test(
     process_stderr_paths.push(svd2rust_err_file);
 
     match t.arch {
-        CortexM | Mips | Msp430 | XtensaLX => {
+        Avr | CortexM | Mips | Msp430 | XtensaLX => {
             // TODO: Give error the path to stderr
             fs::rename(path_helper_base(&chip_dir, &["lib.rs"]), &lib_rs_file)
                 .chain_err(|| "While moving lib.rs file")?
@@ -3,8 +3,7 @@ use std::borrow::Cow;
 
 #[derive(Debug, PartialEq)]
 pub enum Architecture {
-    // TODO: Coming soon!
-    // Avr,
+    Avr,
     CortexM,
     Mips,
     Msp430,
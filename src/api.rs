@@ -0,0 +1,595 @@
+//! Public API of a generated crate
+//!
+//! [`Api`] lists the public items of generated code: modules, types, enum variants, struct
+//! fields, functions, methods, consts, trait impls and re-exports, each with a signature. It is
+//! read either from the `TokenStream` of [`device::render`](crate::generate::device::render) or
+//! from a previously generated tree on disk, so [`diff`] can tell what an svd2rust upgrade changes
+//! in a PAC.
+
+use anyhow::{Context, Result};
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use syn::{ImplItem, Item, TraitItem, Visibility};
+
+/// Kind of a public item
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Module,
+    Struct,
+    Union,
+    Enum,
+    Variant,
+    Field,
+    Type,
+    Trait,
+    Const,
+    Static,
+    Fn,
+    Method,
+    Impl,
+    /// Associated type of a trait impl
+    AssocType,
+    Use,
+    Macro,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Module => "module",
+            Self::Struct => "struct",
+            Self::Union => "union",
+            Self::Enum => "enum",
+            Self::Variant => "variant",
+            Self::Field => "field",
+            Self::Type => "type",
+            Self::Trait => "trait",
+            Self::Const => "const",
+            Self::Static => "static",
+            Self::Fn => "fn",
+            Self::Method => "method",
+            Self::Impl => "impl",
+            Self::AssocType => "associated type",
+            Self::Use => "use",
+            Self::Macro => "macro",
+        })
+    }
+}
+
+/// A public item and its signature, like the argument and return types of a method
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApiItem {
+    pub kind: Kind,
+    pub signature: String,
+}
+
+/// Public items by path, like `tim1::cr1::R::cen`
+#[derive(Clone, Debug, Default)]
+pub struct Api(pub BTreeMap<String, ApiItem>);
+
+impl Api {
+    /// Lists the items of freshly generated code
+    pub fn from_tokens(tokens: TokenStream) -> Result<Self> {
+        let file: syn::File = syn::parse2(tokens).context("Error parsing generated code")?;
+        let mut api = Self::default();
+        api.items(&file.items, "", None)?;
+        Ok(api)
+    }
+
+    /// Lists the items of a generated tree, given either the `lib.rs` (or `mod.rs`) file or the
+    /// crate directory. Modules split into their own files are followed.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let file = if path.is_dir() {
+            ["src/lib.rs", "lib.rs", "src/mod.rs", "mod.rs"]
+                .iter()
+                .map(|f| path.join(f))
+                .find(|f| f.is_file())
+                .with_context(|| format!("No lib.rs or mod.rs in {}", path.display()))?
+        } else {
+            path.to_path_buf()
+        };
+        let dir = file.parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+        let mut api = Self::default();
+        api.items(&parse(&file)?.items, "", Some(&dir))?;
+        Ok(api)
+    }
+
+    fn add(&mut self, path: String, kind: Kind, signature: String) {
+        self.0.insert(path, ApiItem { kind, signature });
+    }
+
+    /// Walks the items of a module, `dir` holds the files of its out-of-line submodules
+    fn items(&mut self, items: &[Item], module: &str, dir: Option<&Path>) -> Result<()> {
+        let path = |name: &dyn fmt::Display| {
+            if module.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}::{}", module, name)
+            }
+        };
+        for item in items {
+            match item {
+                Item::Mod(m) if public(&m.vis) => {
+                    let name = path(&m.ident);
+                    self.add(name.clone(), Kind::Module, String::new());
+                    let subdir = dir.map(|dir| dir.join(m.ident.to_string()));
+                    match (&m.content, dir) {
+                        (Some((_, items)), _) => self.items(items, &name, subdir.as_deref())?,
+                        (None, Some(dir)) => {
+                            let file = module_file(dir, m)?;
+                            self.items(&parse(&file)?.items, &name, subdir.as_deref())?
+                        }
+                        (None, None) => {}
+                    }
+                }
+                Item::Struct(s) if public(&s.vis) => {
+                    let name = path(&s.ident);
+                    self.add(name.clone(), Kind::Struct, tokens(&s.generics));
+                    for f in s.fields.iter().filter(|f| public(&f.vis)) {
+                        if let Some(ident) = &f.ident {
+                            self.add(format!("{}.{}", name, ident), Kind::Field, tokens(&f.ty));
+                        }
+                    }
+                }
+                Item::Union(u) if public(&u.vis) => {
+                    let name = path(&u.ident);
+                    self.add(name.clone(), Kind::Union, tokens(&u.generics));
+                    for f in u.fields.named.iter().filter(|f| public(&f.vis)) {
+                        if let Some(ident) = &f.ident {
+                            self.add(format!("{}.{}", name, ident), Kind::Field, tokens(&f.ty));
+                        }
+                    }
+                }
+                Item::Enum(e) if public(&e.vis) => {
+                    let name = path(&e.ident);
+                    self.add(name.clone(), Kind::Enum, tokens(&e.generics));
+                    for v in &e.variants {
+                        self.add(
+                            format!("{}::{}", name, v.ident),
+                            Kind::Variant,
+                            tokens(&v.fields),
+                        );
+                    }
+                }
+                Item::Type(t) if public(&t.vis) => {
+                    let (generics, ty) = (&t.generics, &t.ty);
+                    self.add(
+                        path(&t.ident),
+                        Kind::Type,
+                        tidy(quote!(#generics = #ty).to_string()),
+                    );
+                }
+                Item::Trait(t) if public(&t.vis) => {
+                    let name = path(&t.ident);
+                    self.add(name.clone(), Kind::Trait, tokens(&t.generics));
+                    for i in &t.items {
+                        match i {
+                            TraitItem::Method(m) => self.add(
+                                format!("{}::{}", name, m.sig.ident),
+                                Kind::Method,
+                                tokens(&m.sig),
+                            ),
+                            TraitItem::Type(ty) => self.add(
+                                format!("{}::{}", name, ty.ident),
+                                Kind::AssocType,
+                                String::new(),
+                            ),
+                            TraitItem::Const(c) => self.add(
+                                format!("{}::{}", name, c.ident),
+                                Kind::Const,
+                                tokens(&c.ty),
+                            ),
+                            _ => {}
+                        }
+                    }
+                }
+                Item::Const(c) if public(&c.vis) => {
+                    self.add(path(&c.ident), Kind::Const, tokens(&c.ty))
+                }
+                Item::Static(s) if public(&s.vis) => {
+                    self.add(path(&s.ident), Kind::Static, tokens(&s.ty))
+                }
+                Item::Fn(f) if public(&f.vis) => {
+                    self.add(path(&f.sig.ident), Kind::Fn, tokens(&f.sig))
+                }
+                Item::Impl(i) => match &i.trait_ {
+                    Some((_, tr, _)) => {
+                        let ty = &i.self_ty;
+                        let name = path(&format!("impl {}", tokens(&quote!(#tr for #ty))));
+                        self.add(name.clone(), Kind::Impl, tokens(&i.generics));
+                        for item in &i.items {
+                            if let ImplItem::Type(t) = item {
+                                self.add(
+                                    format!("{}::{}", name, t.ident),
+                                    Kind::AssocType,
+                                    tokens(&t.ty),
+                                );
+                            }
+                        }
+                    }
+                    None => {
+                        let ty = path(&self_type(&i.self_ty));
+                        for item in &i.items {
+                            match item {
+                                ImplItem::Method(m) if public(&m.vis) => self.add(
+                                    format!("{}::{}", ty, m.sig.ident),
+                                    Kind::Method,
+                                    tokens(&m.sig),
+                                ),
+                                ImplItem::Const(c) if public(&c.vis) => self.add(
+                                    format!("{}::{}", ty, c.ident),
+                                    Kind::Const,
+                                    tokens(&c.ty),
+                                ),
+                                _ => {}
+                            }
+                        }
+                    }
+                },
+                Item::Use(u) if public(&u.vis) => self.add(
+                    path(&format!("use {}", tokens(&u.tree))),
+                    Kind::Use,
+                    String::new(),
+                ),
+                Item::Macro(m) => {
+                    let exported = m.attrs.iter().any(|a| a.path.is_ident("macro_export"));
+                    if let (true, Some(ident)) = (exported, &m.ident) {
+                        self.add(ident.to_string(), Kind::Macro, String::new());
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn public(vis: &Visibility) -> bool {
+    matches!(vis, Visibility::Public(_))
+}
+
+/// Tokens as text, without the spaces `TokenStream` puts around paths and generics
+fn tokens(t: &impl ToTokens) -> String {
+    tidy(t.to_token_stream().to_string())
+}
+
+fn tidy(mut s: String) -> String {
+    for (from, to) in [
+        (" :: ", "::"),
+        (":: ", "::"),
+        (" < ", "<"),
+        ("< ", "<"),
+        (" >", ">"),
+        (" ,", ","),
+        (" : ", ": "),
+        ("& ", "&"),
+        ("( ", "("),
+        (" )", ")"),
+    ] {
+        s = s.replace(from, to);
+    }
+    // Arguments follow the name of the function, tuples keep their space after `:` or `->`
+    let mut out = String::with_capacity(s.len());
+    for (i, c) in s.char_indices() {
+        let args = s[i + 1..].starts_with('(')
+            && !out.ends_with("->")
+            && out.ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '>');
+        if c != ' ' || !args {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Name of the implemented type without its generic arguments, which often change between
+/// versions without changing the API
+fn self_type(ty: &syn::Type) -> String {
+    match ty {
+        syn::Type::Path(p) => match p.path.segments.last() {
+            Some(segment) => segment.ident.to_string(),
+            None => tokens(ty),
+        },
+        _ => tokens(ty),
+    }
+}
+
+fn parse(file: &Path) -> Result<syn::File> {
+    let text =
+        std::fs::read_to_string(file).with_context(|| format!("Cannot read {}", file.display()))?;
+    syn::parse_file(&text).with_context(|| format!("Error parsing {}", file.display()))
+}
+
+/// File of an out-of-line module declared in a file whose submodules are in `dir`
+fn module_file(dir: &Path, m: &syn::ItemMod) -> Result<PathBuf> {
+    for attr in &m.attrs {
+        if let Ok(syn::Meta::NameValue(nv)) = attr.parse_meta() {
+            if let (true, syn::Lit::Str(s)) = (nv.path.is_ident("path"), &nv.lit) {
+                return Ok(dir.join(s.value()));
+            }
+        }
+    }
+    let name = m.ident.to_string();
+    [
+        dir.join(format!("{}.rs", name)),
+        dir.join(&name).join("mod.rs"),
+    ]
+    .into_iter()
+    .find(|f| f.is_file())
+    .with_context(|| format!("Cannot find the file of module {}", name))
+}
+
+/// What happened to a public item
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    /// Signature changed
+    Changed,
+}
+
+/// Change of one public item
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApiChange {
+    pub change: Change,
+    pub kind: Kind,
+    pub path: String,
+    /// Old and new signatures of changed items
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub signatures: Option<(String, String)>,
+}
+
+impl ApiChange {
+    /// Whether users of the item can break
+    pub fn is_breaking(&self) -> bool {
+        self.change != Change::Added
+    }
+}
+
+impl fmt::Display for ApiChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let change = match self.change {
+            Change::Added => "added",
+            Change::Removed => "removed",
+            Change::Changed => "changed",
+        };
+        match self.kind {
+            // The path already tells it's an impl
+            Kind::Impl => write!(f, "{} {}", change, self.path)?,
+            kind => write!(f, "{} {} {}", change, kind, self.path)?,
+        }
+        if let Some((old, new)) = &self.signatures {
+            write!(f, "\n    was: {}\n    now: {}", old, new)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares the `old` and `new` APIs, ordered by path
+pub fn diff(old: &Api, new: &Api) -> Vec<ApiChange> {
+    let mut changes = Vec::new();
+    for (path, item) in &old.0 {
+        match new.0.get(path) {
+            None => changes.push(ApiChange {
+                change: Change::Removed,
+                kind: item.kind,
+                path: path.clone(),
+                signatures: None,
+            }),
+            Some(other) if other.signature != item.signature || other.kind != item.kind => changes
+                .push(ApiChange {
+                    change: Change::Changed,
+                    kind: other.kind,
+                    path: path.clone(),
+                    signatures: Some((item.signature.clone(), other.signature.clone())),
+                }),
+            Some(_) => {}
+        }
+    }
+    for (path, item) in &new.0 {
+        if !old.0.contains_key(path) {
+            changes.push(ApiChange {
+                change: Change::Added,
+                kind: item.kind,
+                path: path.clone(),
+                signatures: None,
+            });
+        }
+    }
+    // Items of added and removed modules come and go with them
+    let modules = changes
+        .iter()
+        .filter(|c| c.kind == Kind::Module && c.change != Change::Changed)
+        .map(|c| format!("{}::", c.path))
+        .collect::<Vec<_>>();
+    changes.retain(|c| !modules.iter().any(|m| c.path.starts_with(m)));
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(api: &Api) -> Vec<(&str, Kind, &str)> {
+        api.0
+            .iter()
+            .map(|(path, item)| (path.as_str(), item.kind, item.signature.as_str()))
+            .collect()
+    }
+
+    fn api(tokens: TokenStream) -> Api {
+        Api::from_tokens(tokens).unwrap()
+    }
+
+    #[test]
+    fn from_tokens() {
+        let api = api(quote! {
+            pub mod m {
+                pub struct S<T> { pub f: Vec<T>, g: u8 }
+                impl S<u8> {
+                    pub fn new(x: &u8) -> Self { todo!() }
+                    fn private() {}
+                    pub const C: u32 = 1;
+                }
+                impl core::ops::Deref for S<u8> {
+                    type Target = u8;
+                    fn deref(&self) -> &u8 { todo!() }
+                }
+                pub enum E { A, B(u8) }
+            }
+            mod private {
+                pub struct Hidden;
+            }
+            #[macro_export]
+            macro_rules! mac { () => {}; }
+            pub use m::S as T;
+        });
+        assert_eq!(
+            items(&api),
+            [
+                ("m", Kind::Module, ""),
+                ("m::E", Kind::Enum, ""),
+                ("m::E::A", Kind::Variant, ""),
+                ("m::E::B", Kind::Variant, "(u8)"),
+                ("m::S", Kind::Struct, "<T>"),
+                ("m::S.f", Kind::Field, "Vec<T>"),
+                ("m::S::C", Kind::Const, "u32"),
+                ("m::S::new", Kind::Method, "fn new(x: &u8) -> Self"),
+                ("m::impl core::ops::Deref for S<u8>", Kind::Impl, ""),
+                (
+                    "m::impl core::ops::Deref for S<u8>::Target",
+                    Kind::AssocType,
+                    "u8"
+                ),
+                ("mac", Kind::Macro, ""),
+                ("use m::S as T", Kind::Use, ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn tidy_signatures() {
+        let sig = |t: TokenStream| tokens(&t);
+        assert_eq!(
+            sig(quote!(fn f<T: Copy>(x: &T, y: (u8, u16)) -> core::cell::Cell<T>)),
+            "fn f<T: Copy>(x: &T, y: (u8, u16)) -> core::cell::Cell<T>"
+        );
+        assert_eq!(
+            sig(quote!(fn free(self) -> (DMA1, DMA2,))),
+            "fn free(self) -> (DMA1, DMA2,)"
+        );
+        assert_eq!(
+            sig(quote!(fn write<F>(&'a mut self, f: F) where F: FnOnce(&mut W) -> &mut W)),
+            "fn write<F>(&'a mut self, f: F) where F: FnOnce(&mut W) -> &mut W"
+        );
+    }
+
+    #[test]
+    fn changes() {
+        let old = api(quote! {
+            pub mod kept {
+                pub fn f(x: u8) {}
+                pub fn g() {}
+                pub struct K;
+            }
+            pub mod gone {
+                pub struct A;
+                pub mod inner { pub fn h() {} }
+            }
+        });
+        let new = api(quote! {
+            pub mod kept {
+                pub fn f(x: u16) {}
+                pub fn k() {}
+                pub enum K {}
+            }
+            pub mod new {
+                pub struct B;
+            }
+        });
+        let changes = diff(&old, &new);
+        // Only the added and removed modules are listed, not their items
+        assert_eq!(
+            changes
+                .iter()
+                .map(|c| (c.change, c.kind, c.path.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (Change::Removed, Kind::Module, "gone"),
+                (Change::Changed, Kind::Enum, "kept::K"),
+                (Change::Changed, Kind::Fn, "kept::f"),
+                (Change::Removed, Kind::Fn, "kept::g"),
+                (Change::Added, Kind::Fn, "kept::k"),
+                (Change::Added, Kind::Module, "new"),
+            ]
+        );
+        assert_eq!(
+            changes[2].signatures,
+            Some(("fn f(x: u8)".to_string(), "fn f(x: u16)".to_string()))
+        );
+        assert_eq!(
+            changes[2].to_string(),
+            "changed fn kept::f\n    was: fn f(x: u8)\n    now: fn f(x: u16)"
+        );
+        assert!(changes[0].is_breaking());
+        assert!(!changes[4].is_breaking());
+        assert!(diff(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn from_path() {
+        let dir = std::env::temp_dir().join(format!("svd2rust-api-{}", std::process::id()));
+        let files = [
+            (
+                "src/lib.rs",
+                "pub mod a; #[path = \"other.rs\"] pub mod b; pub mod c { pub mod d; } mod e;",
+            ),
+            ("src/a/mod.rs", "pub mod inner;"),
+            ("src/a/inner.rs", "pub fn f() {}"),
+            ("src/other.rs", "pub struct B;"),
+            ("src/c/d.rs", "pub const D: u8 = 0;"),
+        ];
+        for (file, text) in files {
+            let file = dir.join(file);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, text).unwrap();
+        }
+        // The crate directory or its `lib.rs`
+        let api = Api::from_path(&dir).unwrap();
+        let lib = Api::from_path(&dir.join("src/lib.rs")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "pub mod missing;").unwrap();
+        let missing = Api::from_path(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let paths = api.0.keys().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "a",
+                "a::inner",
+                "a::inner::f",
+                "b",
+                "b::B",
+                "c",
+                "c::d",
+                "c::d::D"
+            ]
+        );
+        assert_eq!(lib.0, api.0);
+        assert!(missing.is_err());
+    }
+}
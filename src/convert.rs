@@ -0,0 +1,343 @@
+//! Writing a loaded [`Device`] back to SVD XML, YAML or JSON
+//!
+//! The device is written as the generator sees it, after [`load_from`](crate::load_from) has
+//! parsed it and propagated register properties.
+
+use crate::svd::Device;
+use crate::util::SourceType;
+use anyhow::{bail, Context, Result};
+
+/// Serializes `device` in the given format
+pub fn encode_to(device: &Device, format: SourceType) -> Result<String> {
+    Ok(match format {
+        SourceType::Xml => {
+            svd_encoder::encode(device).with_context(|| "Error encoding SVD XML".to_string())?
+        }
+        #[cfg(feature = "yaml")]
+        SourceType::Yaml => {
+            let device = &explicit_dim_index(device);
+            // Tagged YAML enums can't be read back by the flattened `svd-rs` types, use maps
+            let mut yaml = Vec::new();
+            serde_yaml::with::singleton_map_recursive::serialize(
+                device,
+                &mut serde_yaml::Serializer::new(&mut yaml),
+            )
+            .with_context(|| "Error encoding SVD YAML".to_string())?;
+            String::from_utf8(yaml)?
+        }
+        #[cfg(feature = "json")]
+        SourceType::Json => serde_json::to_string_pretty(&explicit_dim_index(device))
+            .with_context(|| "Error encoding SVD JSON".to_string())?,
+        #[allow(unreachable_patterns)]
+        format => bail!("Writing {:?} files is not supported", format),
+    })
+}
+
+/// `svd-rs` drops a deserialized `dimElement` without `dimIndex`, write the default indexes out
+#[cfg(any(feature = "json", feature = "yaml"))]
+fn explicit_dim_index(device: &Device) -> Device {
+    use crate::svd::{Cluster, DimElement, Field, Peripheral, Register, RegisterCluster};
+
+    fn fill(dim: &mut DimElement) {
+        if dim.dim_index.is_none() {
+            dim.dim_index = Some((0..dim.dim).map(|i| i.to_string()).collect());
+        }
+    }
+    fn items(list: &mut [RegisterCluster]) {
+        for item in list {
+            match item {
+                RegisterCluster::Register(r) => {
+                    if let Register::Array(_, dim) = r {
+                        fill(dim);
+                    }
+                    for f in r.fields.iter_mut().flatten() {
+                        if let Field::Array(_, dim) = f {
+                            fill(dim);
+                        }
+                    }
+                }
+                RegisterCluster::Cluster(c) => {
+                    if let Cluster::Array(_, dim) = c {
+                        fill(dim);
+                    }
+                    items(&mut c.children);
+                }
+            }
+        }
+    }
+
+    let mut device = device.clone();
+    for p in &mut device.peripherals {
+        if let Peripheral::Array(_, dim) = p {
+            fill(dim);
+        }
+        items(p.registers.as_mut().map_or(&mut [], |r| r.as_mut_slice()));
+    }
+    device
+}
+
+/// Applies a merge patch written in YAML or JSON to `device`
+///
+/// The patch mirrors the serialized device. Object members replace or are merged into the ones of
+/// the device and `null` removes them. Lists of named items (peripherals, registers, fields,
+/// enumerated values, ...) are merged by `name`: a patch item updates the item with the same
+/// name, `_delete: true` removes it, and items with new names are appended. Other lists are
+/// replaced.
+#[cfg(feature = "json")]
+pub fn patch(device: &mut Device, patch: &str, format: SourceType) -> Result<()> {
+    use serde_json::Value;
+
+    let patch: Value = match format {
+        SourceType::Json => {
+            serde_json::from_str(patch).with_context(|| "Error parsing JSON patch")?
+        }
+        #[cfg(feature = "yaml")]
+        SourceType::Yaml => {
+            serde_yaml::from_str(patch).with_context(|| "Error parsing YAML patch")?
+        }
+        format => bail!("Patches can't be written in {:?}", format),
+    };
+    let mut value = serde_json::to_value(&*device)?;
+    merge(&mut value, patch);
+    *device = serde_json::from_value(value)
+        .map_err(|e| anyhow::anyhow!("Patched device is invalid: {}", e))?;
+    svd_parser::expand_properties(device);
+    Ok(())
+}
+
+#[cfg(feature = "json")]
+fn merge(target: &mut serde_json::Value, patch: serde_json::Value) {
+    use serde_json::Value;
+
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(&key);
+                } else {
+                    merge(target.entry(key).or_insert(Value::Null), value);
+                }
+            }
+        }
+        (Value::Array(target), Value::Array(patch))
+            if patch.iter().all(|item| item_name(item).is_some()) =>
+        {
+            for mut item in patch {
+                let name = item_name(&item).unwrap().to_string();
+                let delete = item_body(&mut item)
+                    .and_then(|body| body.remove("_delete"))
+                    .map_or(false, |d| d == Value::Bool(true));
+                let pos = target.iter().position(|t| item_name(t) == Some(&name));
+                match (pos, delete) {
+                    (Some(pos), true) => {
+                        target.remove(pos);
+                    }
+                    (Some(pos), false) => merge(&mut target[pos], item),
+                    (None, false) => target.push(item),
+                    (None, true) => {}
+                }
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+/// Object holding the `name` of a list item, registers and clusters are wrapped in a single member
+/// object telling their kind
+#[cfg(feature = "json")]
+fn item_body(
+    item: &mut serde_json::Value,
+) -> Option<&mut serde_json::Map<String, serde_json::Value>> {
+    let object = item.as_object_mut()?;
+    if object.contains_key("name") {
+        return Some(object);
+    }
+    if object.len() == 1 {
+        return object.values_mut().next()?.as_object_mut();
+    }
+    None
+}
+
+#[cfg(feature = "json")]
+fn item_name(item: &serde_json::Value) -> Option<&str> {
+    let object = item.as_object()?;
+    match object.get("name") {
+        Some(name) => name.as_str(),
+        None if object.len() == 1 => object.values().next()?.get("name")?.as_str(),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Config;
+
+    const SVD: &str = r#"<device schemaVersion="1.1">
+  <name>DEV</name>
+  <version>1.0</version>
+  <description>Device</description>
+  <addressUnitBits>8</addressUnitBits>
+  <width>32</width>
+  <size>32</size>
+  <access>read-write</access>
+  <resetValue>0</resetValue>
+  <resetMask>0xFFFFFFFF</resetMask>
+  <peripherals>
+    <peripheral>
+      <dim>2</dim>
+      <dimIncrement>0x100</dimIncrement>
+      <name>UART%s</name>
+      <description>UART</description>
+      <baseAddress>0x40000000</baseAddress>
+      <addressBlock><offset>0</offset><size>0x100</size><usage>registers</usage></addressBlock>
+      <interrupt><name>UART</name><value>3</value></interrupt>
+      <registers>
+        <register>
+          <name>CR</name>
+          <description>Control</description>
+          <addressOffset>0</addressOffset>
+          <fields>
+            <field>
+              <name>EN</name>
+              <description>Enable</description>
+              <bitRange>[0:0]</bitRange>
+            </field>
+            <field>
+              <name>MODE</name>
+              <description>Mode</description>
+              <bitRange>[2:1]</bitRange>
+              <enumeratedValues>
+                <enumeratedValue><name>OFF</name><value>0</value></enumeratedValue>
+                <enumeratedValue><name>FAST</name><value>2</value></enumeratedValue>
+              </enumeratedValues>
+            </field>
+            <field>
+              <dim>2</dim>
+              <dimIncrement>1</dimIncrement>
+              <name>IE%s</name>
+              <description>Interrupt enable</description>
+              <bitRange>[4:3]</bitRange>
+            </field>
+          </fields>
+        </register>
+        <cluster>
+          <dim>2</dim>
+          <dimIncrement>8</dimIncrement>
+          <dimIndex>A,B</dimIndex>
+          <name>CH%s</name>
+          <description>Channel</description>
+          <addressOffset>0x10</addressOffset>
+          <register>
+            <dim>2</dim>
+            <dimIncrement>4</dimIncrement>
+            <name>DR%s</name>
+            <description>Data</description>
+            <addressOffset>0</addressOffset>
+            <access>read-only</access>
+          </register>
+        </cluster>
+      </registers>
+    </peripheral>
+    <peripheral derivedFrom="UART%s">
+      <name>LPUART</name>
+      <baseAddress>0x40001000</baseAddress>
+    </peripheral>
+  </peripherals>
+</device>"#;
+
+    fn load(text: &str, format: SourceType) -> Device {
+        let config = Config {
+            source_type: format,
+            ..Config::default()
+        };
+        crate::load_from(text, &config).unwrap()
+    }
+
+    #[test]
+    fn xml() {
+        let device = load(SVD, SourceType::Xml);
+        let xml = encode_to(&device, SourceType::Xml).unwrap();
+        assert_eq!(load(&xml, SourceType::Xml), device);
+    }
+
+    /// Writes the device in `format`, reads it back and goes through XML again. The schema
+    /// version is not serialized, the one of `SVD` is the default.
+    #[cfg(any(feature = "json", feature = "yaml"))]
+    fn round_trip(format: SourceType) {
+        let device = load(SVD, SourceType::Xml);
+        let back = load(&encode_to(&device, format).unwrap(), format);
+        // The default `dimIndex` of arrays is written out
+        assert_eq!(back, explicit_dim_index(&device));
+        let xml = encode_to(&back, SourceType::Xml).unwrap();
+        assert_eq!(load(&xml, SourceType::Xml), back);
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml() {
+        round_trip(SourceType::Yaml);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json() {
+        round_trip(SourceType::Json);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn patch_by_name() {
+        use crate::svd::Access;
+
+        let mut device = load(SVD, SourceType::Xml);
+        let json = r#"{
+  "peripherals": [{
+    "name": "UART%s",
+    "description": "Patched",
+    "addressBlock": [{ "offset": 0, "size": 512, "usage": "registers" }],
+    "interrupt": [{ "name": "UART_RX", "value": 4 }],
+    "registers": [
+      { "register": {
+        "name": "CR",
+        "fields": [
+          { "name": "EN", "_delete": true },
+          { "name": "MODE", "description": null },
+          { "name": "BUSY", "description": "Busy", "bitOffset": 7, "bitWidth": 1 }
+        ]
+      }},
+      { "register": { "name": "SR", "description": "Status", "addressOffset": 4 } }
+    ]
+  }]
+}"#;
+        patch(&mut device, json, SourceType::Json).unwrap();
+        let uart = &device.peripherals[0];
+        assert_eq!(uart.description.as_deref(), Some("Patched"));
+        // Lists of unnamed items are replaced, named ones are merged
+        let blocks = uart.address_block.as_deref().unwrap();
+        assert_eq!(blocks.iter().map(|b| b.size).collect::<Vec<_>>(), [512]);
+        let interrupts = uart.interrupt.iter().map(|i| i.name.as_str());
+        assert_eq!(interrupts.collect::<Vec<_>>(), ["UART", "UART_RX"]);
+        let cr = uart.get_register("CR").unwrap();
+        let fields = cr.fields().map(|f| f.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(fields, ["MODE", "IE%s", "BUSY"]);
+        let mode = cr.get_field("MODE").unwrap();
+        assert_eq!(mode.description, None);
+        assert_eq!(mode.enumerated_values[0].values.len(), 2);
+        // The properties of new registers are propagated from the device
+        let sr = uart.get_register("SR").unwrap();
+        assert_eq!(sr.properties.size, Some(32));
+        assert_eq!(sr.properties.access, Some(Access::ReadWrite));
+        // The other items are kept
+        assert!(uart.get_cluster("CH%s").is_some());
+        assert_eq!(device.peripherals[1].name, "LPUART");
+
+        let yaml = "peripherals:\n- name: LPUART\n  _delete: true\n";
+        #[cfg(feature = "yaml")]
+        {
+            patch(&mut device, yaml, SourceType::Yaml).unwrap();
+            assert_eq!(device.peripherals.len(), 1);
+        }
+        assert!(patch(&mut device, yaml, SourceType::Xml).is_err());
+    }
+}
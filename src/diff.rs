@@ -0,0 +1,563 @@
+//! Changes between two revisions of a device and their effect on the generated API
+//!
+//! [`diff`] compares the expanded devices item by item: peripherals, clusters, registers, fields
+//! and enumerated values. An item that disappears while another one of the same kind appears at
+//! the same place under the same parent is reported as renamed. Each [`Change`] carries the
+//! semver [`Impact`] it has on the generated crate.
+
+use crate::svd::{Access, Device, Field, Register, RegisterCluster};
+use crate::util;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+/// Version bump the generated crate needs for a change
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Impact {
+    /// Same API, different behavior, like a moved field
+    Patch,
+    /// API additions
+    Minor,
+    /// API removals or changes
+    Major,
+}
+
+impl fmt::Display for Impact {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Patch => "patch",
+            Self::Minor => "minor",
+            Self::Major => "major",
+        })
+    }
+}
+
+/// Kind of a compared item
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Item {
+    Peripheral,
+    Cluster,
+    Register,
+    Field,
+    /// Enumerated value of a field
+    Variant,
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Peripheral => "peripheral",
+            Self::Cluster => "cluster",
+            Self::Register => "register",
+            Self::Field => "field",
+            Self::Variant => "variant",
+        })
+    }
+}
+
+/// What happened to an item
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Added,
+    Removed,
+    Renamed,
+    /// Address, bit offset or value changed
+    Moved,
+    /// Size or bit width changed
+    Resized,
+    /// Access changed
+    Access,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Renamed => "renamed",
+            Self::Moved => "moved",
+            Self::Resized => "resized",
+            Self::Access => "access",
+        })
+    }
+}
+
+/// A change of one item
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    pub impact: Impact,
+    pub kind: Kind,
+    pub item: Item,
+    /// Dot separated path of the item in the old device, in the new one for added items
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} {} {}",
+            self.impact, self.kind, self.item, self.path
+        )?;
+        if !self.message.is_empty() {
+            write!(f, ", {}", self.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Highest impact of `changes`, `None` if there are none
+pub fn impact(changes: &[Change]) -> Option<Impact> {
+    changes.iter().map(|c| c.impact).max()
+}
+
+/// Compares the `old` and `new` revisions of a device
+pub fn diff(old: &Device, new: &Device) -> Result<Vec<Change>> {
+    let old = items(
+        &svd_parser::expand(old).context("Error expanding old device")?,
+        &derived(old),
+    );
+    let new = items(
+        &svd_parser::expand(new).context("Error expanding new device")?,
+        &derived(new),
+    );
+
+    let mut changes = Vec::new();
+    // Paths in the new device of the old items kept, renamed or not
+    let mut kept = HashMap::<&str, String>::new();
+    let max_depth = old.keys().chain(new.keys()).map(|p| depth(p)).max();
+    for d in 1..=max_depth.unwrap_or(0) {
+        // Old items at this depth with the path they would have under their new parents
+        let moved = old
+            .iter()
+            .filter(|(path, _)| depth(path) == d)
+            .map(|(path, item)| {
+                let translated = match path.rsplit_once('.') {
+                    Some((parent, name)) => kept
+                        .get(parent)
+                        .map(|parent| format!("{}.{}", parent, name)),
+                    None => Some(path.clone()),
+                };
+                (path, item, translated)
+            })
+            .collect::<Vec<_>>();
+        let translated = moved
+            .iter()
+            .filter_map(|(_, _, t)| t.as_deref())
+            .collect::<HashSet<_>>();
+        let mut added = new
+            .iter()
+            .filter(|(path, _)| depth(path) == d && !translated.contains(path.as_str()))
+            .collect::<Vec<_>>();
+
+        for (path, item, translated) in moved {
+            if let Some(other) = translated.as_ref().and_then(|t| new.get(t)) {
+                compare(path, item, other, &mut changes);
+                kept.insert(path, translated.unwrap());
+                continue;
+            }
+            // Children of a removed parent are removed with it
+            let parent = path.rsplit_once('.').map(|(parent, _)| parent);
+            if parent.map_or(false, |parent| !kept.contains_key(parent)) {
+                continue;
+            }
+            let new_parent = parent.map(|parent| kept[parent].as_str());
+            let renamed = added.iter().position(|(p, a)| {
+                a.item == item.item
+                    && a.place == item.place
+                    && a.size == item.size
+                    && p.rsplit_once('.').map(|(parent, _)| parent) == new_parent
+            });
+            match renamed {
+                Some(i) => {
+                    let (new_path, other) = added.remove(i);
+                    changes.push(Change {
+                        impact: Impact::Major,
+                        kind: Kind::Renamed,
+                        item: item.item,
+                        path: path.clone(),
+                        message: format!("now `{}`", new_path),
+                    });
+                    compare(path, item, other, &mut changes);
+                    kept.insert(path, new_path.clone());
+                }
+                None => changes.push(Change {
+                    impact: Impact::Major,
+                    kind: Kind::Removed,
+                    item: item.item,
+                    path: path.clone(),
+                    message: String::new(),
+                }),
+            }
+        }
+        let parents = kept.values().map(String::as_str).collect::<HashSet<_>>();
+        for (path, item) in added {
+            // Children of an added parent are added with it
+            let parent = path.rsplit_once('.').map(|(parent, _)| parent);
+            if parent.map_or(false, |parent| !parents.contains(parent)) {
+                continue;
+            }
+            changes.push(Change {
+                impact: Impact::Minor,
+                kind: Kind::Added,
+                item: item.item,
+                path: path.clone(),
+                message: String::new(),
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(changes)
+}
+
+/// Derived peripherals reuse the registers of the one they derive from, don't repeat their changes
+fn derived(device: &Device) -> HashSet<&str> {
+    device
+        .peripherals
+        .iter()
+        .filter(|p| p.derived_from.is_some())
+        .map(|p| p.name.as_str())
+        .collect()
+}
+
+/// Compared properties of an item
+struct Props {
+    item: Item,
+    /// Address of peripherals, clusters and registers, bit offset of fields, value of variants
+    place: u64,
+    /// Size in bits of registers and fields
+    size: u32,
+    access: Option<Access>,
+}
+
+fn compare(path: &str, old: &Props, new: &Props, changes: &mut Vec<Change>) {
+    let mut change = |impact, kind, message| {
+        changes.push(Change {
+            impact,
+            kind,
+            item: old.item,
+            path: path.to_string(),
+            message,
+        })
+    };
+    if old.place != new.place {
+        // A new address changes the layout of register blocks and the address constants
+        let (impact, message) = match old.item {
+            Item::Field => (Impact::Patch, format!("bit {} -> {}", old.place, new.place)),
+            Item::Variant => (
+                Impact::Patch,
+                format!("value {} -> {}", old.place, new.place),
+            ),
+            _ => (
+                Impact::Minor,
+                format!("{:#x} -> {:#x}", old.place, new.place),
+            ),
+        };
+        change(impact, Kind::Moved, message);
+    }
+    if old.size != new.size {
+        change(
+            Impact::Major,
+            Kind::Resized,
+            format!("{} -> {} bits", old.size, new.size),
+        );
+    }
+    if let (Some(a), Some(b)) = (old.access, new.access) {
+        if a != b {
+            // Lost read or write methods break users, gained ones don't
+            let impact = if (a.can_read() && !b.can_read()) || (a.can_write() && !b.can_write()) {
+                Impact::Major
+            } else {
+                Impact::Minor
+            };
+            change(
+                impact,
+                Kind::Access,
+                format!("{} -> {}", a.as_str(), b.as_str()),
+            );
+        }
+    }
+}
+
+fn depth(path: &str) -> usize {
+    path.split('.').count()
+}
+
+/// All items of an expanded device by path
+fn items(device: &Device, derived: &HashSet<&str>) -> BTreeMap<String, Props> {
+    let mut out = BTreeMap::new();
+    for p in &device.peripherals {
+        let address = p.base_address;
+        out.insert(
+            p.name.clone(),
+            Props {
+                item: Item::Peripheral,
+                place: address,
+                size: 0,
+                access: None,
+            },
+        );
+        if !derived.contains(p.name.as_str()) {
+            block(
+                p.registers.as_deref().unwrap_or_default(),
+                &p.name,
+                address,
+                &mut out,
+            );
+        }
+    }
+    out
+}
+
+fn block(items: &[RegisterCluster], path: &str, base: u64, out: &mut BTreeMap<String, Props>) {
+    for item in items {
+        match item {
+            RegisterCluster::Register(r) => register(r, path, base, out),
+            RegisterCluster::Cluster(c) => {
+                let path = format!("{}.{}", path, c.name);
+                let address = base + u64::from(c.address_offset);
+                out.insert(
+                    path.clone(),
+                    Props {
+                        item: Item::Cluster,
+                        place: address,
+                        size: 0,
+                        access: None,
+                    },
+                );
+                block(&c.children, &path, address, out);
+            }
+        }
+    }
+}
+
+fn register(r: &Register, path: &str, base: u64, out: &mut BTreeMap<String, Props>) {
+    let path = format!("{}.{}", path, r.name);
+    let access = util::access_of(&r.properties, r.fields.as_deref());
+    out.insert(
+        path.clone(),
+        Props {
+            item: Item::Register,
+            place: base + u64::from(r.address_offset),
+            size: r.properties.size.unwrap_or(32),
+            access: Some(access),
+        },
+    );
+    for f in r.fields.as_deref().unwrap_or_default() {
+        field(f, &path, access, out);
+    }
+}
+
+fn field(f: &Field, path: &str, access: Access, out: &mut BTreeMap<String, Props>) {
+    let path = format!("{}.{}", path, f.name);
+    for ev in f.enumerated_values.iter().flat_map(|evs| &evs.values) {
+        // Read and write sets may share variants, keep the first
+        out.entry(format!("{}.{}", path, ev.name)).or_insert(Props {
+            item: Item::Variant,
+            place: ev.value.unwrap_or(u64::MAX),
+            size: 0,
+            access: None,
+        });
+    }
+    out.insert(
+        path,
+        Props {
+            item: Item::Field,
+            place: u64::from(f.bit_offset()),
+            size: f.bit_width(),
+            access: Some(f.access.unwrap_or(access)),
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Device with a peripheral `P` at `base` holding `registers`, and a peripheral `Q`
+    fn device(base: u32, registers: &str) -> Device {
+        let xml = format!(
+            r#"<device>
+  <name>DEV</name>
+  <addressUnitBits>8</addressUnitBits>
+  <width>32</width>
+  <size>32</size>
+  <access>read-write</access>
+  <peripherals>
+    <peripheral>
+      <name>P</name>
+      <baseAddress>{base:#x}</baseAddress>
+      <registers>{registers}</registers>
+    </peripheral>
+    <peripheral>
+      <name>Q</name>
+      <baseAddress>0x2000</baseAddress>
+      <registers>
+        <register><name>DATA</name><addressOffset>0</addressOffset></register>
+      </registers>
+    </peripheral>
+  </peripherals>
+</device>"#
+        );
+        svd_parser::parse(&xml).unwrap()
+    }
+
+    fn register(name: &str, offset: u32, size: u32, access: &str, fields: &str) -> String {
+        format!(
+            "<register><name>{name}</name><addressOffset>{offset}</addressOffset>\
+             <size>{size}</size><access>{access}</access><fields>{fields}</fields></register>"
+        )
+    }
+
+    fn field(name: &str, offset: u32, width: u32) -> String {
+        format!(
+            "<field><name>{name}</name><bitOffset>{offset}</bitOffset>\
+             <bitWidth>{width}</bitWidth></field>"
+        )
+    }
+
+    fn changes(old: &Device, new: &Device) -> Vec<(Impact, Kind, String)> {
+        diff(old, new)
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.impact, c.kind, c.path))
+            .collect()
+    }
+
+    #[test]
+    fn unchanged() {
+        let regs = register("CR", 0, 32, "read-write", &field("EN", 0, 1));
+        assert!(diff(&device(0x1000, &regs), &device(0x1000, &regs))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn renamed() {
+        let old = device(
+            0x1000,
+            &register("CR", 0, 32, "read-write", &field("EN", 0, 1)),
+        );
+        let new = device(
+            0x1000,
+            &register("CTRL", 0, 32, "read-write", &field("ENABLE", 0, 1)),
+        );
+        let renamed = diff(&old, &new).unwrap();
+        // The field is compared under the renamed register
+        assert_eq!(
+            renamed
+                .iter()
+                .map(|c| (c.kind, c.path.as_str(), c.message.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (Kind::Renamed, "P.CR", "now `P.CTRL`"),
+                (Kind::Renamed, "P.CR.EN", "now `P.CTRL.ENABLE`"),
+            ]
+        );
+        assert_eq!(impact(&renamed), Some(Impact::Major));
+
+        // A register at another offset is not a rename
+        let new = device(0x1000, &register("CTRL", 4, 32, "read-write", ""));
+        assert_eq!(
+            changes(&old, &new),
+            [
+                (Impact::Major, Kind::Removed, "P.CR".to_string()),
+                (Impact::Minor, Kind::Added, "P.CTRL".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn moved() {
+        let fields = field("EN", 0, 1);
+        let old = device(0x1000, &register("CR", 0, 32, "read-write", &fields));
+        // The register stays at the same offset of the moved peripheral
+        let new = device(
+            0x1100,
+            &register("CR", 4, 32, "read-write", &field("EN", 3, 1)),
+        );
+        assert_eq!(
+            changes(&old, &new),
+            [
+                (Impact::Minor, Kind::Moved, "P".to_string()),
+                (Impact::Minor, Kind::Moved, "P.CR".to_string()),
+                (Impact::Patch, Kind::Moved, "P.CR.EN".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn resized_and_access() {
+        let old = device(
+            0x1000,
+            &format!(
+                "{}{}",
+                register("CR", 0, 32, "read-write", &field("EN", 0, 1)),
+                register("SR", 4, 32, "read-only", "")
+            ),
+        );
+        let new = device(
+            0x1000,
+            &format!(
+                "{}{}",
+                register("CR", 0, 32, "write-only", &field("EN", 0, 2)),
+                register("SR", 4, 32, "read-write", "")
+            ),
+        );
+        assert_eq!(
+            changes(&old, &new),
+            [
+                (Impact::Major, Kind::Access, "P.CR".to_string()),
+                (Impact::Major, Kind::Resized, "P.CR.EN".to_string()),
+                (Impact::Major, Kind::Access, "P.CR.EN".to_string()),
+                (Impact::Minor, Kind::Access, "P.SR".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn added_and_removed() {
+        let old = device(0x1000, &register("CR", 0, 32, "read-write", ""));
+        let new = device(
+            0x1000,
+            &format!(
+                "{}{}",
+                register("CR", 0, 32, "read-write", &field("EN", 0, 1)),
+                register("SR", 4, 32, "read-only", &field("BUSY", 0, 1))
+            ),
+        );
+        // The fields of an added register come with it
+        assert_eq!(
+            changes(&old, &new),
+            [
+                (Impact::Minor, Kind::Added, "P.CR.EN".to_string()),
+                (Impact::Minor, Kind::Added, "P.SR".to_string()),
+            ]
+        );
+        assert_eq!(
+            changes(&new, &old),
+            [
+                (Impact::Major, Kind::Removed, "P.CR.EN".to_string()),
+                (Impact::Major, Kind::Removed, "P.SR".to_string()),
+            ]
+        );
+        assert_eq!(impact(&[]), None);
+    }
+}
@@ -34,8 +34,53 @@ impl<T, const C: usize, const S: usize> ArrayProxy<T, C, S> {
     pub fn len(&self) -> usize {
         C
     }
+    /// Iterate over the items.
+    pub fn iter(&self) -> ArrayProxyIter<'_, T, C, S> {
+        ArrayProxyIter {
+            proxy: self,
+            range: 0..C,
+        }
+    }
+    /// Iterate over the items together with their index.
+    pub fn enumerate(&self) -> core::iter::Enumerate<ArrayProxyIter<'_, T, C, S>> {
+        self.iter().enumerate()
+    }
+}
+
+impl<'a, T, const C: usize, const S: usize> IntoIterator for &'a ArrayProxy<T, C, S> {
+    type Item = &'a T;
+    type IntoIter = ArrayProxyIter<'a, T, C, S>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the items of an [ArrayProxy].
+pub struct ArrayProxyIter<'a, T, const C: usize, const S: usize> {
+    proxy: &'a ArrayProxy<T, C, S>,
+    range: core::ops::Range<usize>,
 }
 
+impl<'a, T, const C: usize, const S: usize> Iterator for ArrayProxyIter<'a, T, C, S> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<&'a T> {
+        let proxy = self.proxy;
+        self.range.next().map(|i| unsafe { proxy.get_ref(i) })
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<'a, T, const C: usize, const S: usize> DoubleEndedIterator for ArrayProxyIter<'a, T, C, S> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        let proxy = self.proxy;
+        self.range.next_back().map(|i| unsafe { proxy.get_ref(i) })
+    }
+}
+
+impl<'a, T, const C: usize, const S: usize> ExactSizeIterator for ArrayProxyIter<'a, T, C, S> {}
+
 impl<T, const C: usize, const S: usize> core::ops::Index<usize> for ArrayProxy<T, C, S> {
     type Output = T;
     fn index(&self, index: usize) -> &T {
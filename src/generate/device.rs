@@ -9,12 +9,19 @@ use std::io::Write;
 
 use crate::util::{self, Config, ToSanitizedCase, U32Ext};
 use crate::Target;
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Result};
 
-use crate::generate::{interrupt, peripheral};
+use crate::generate::{errors::RenderErrors, interrupt, peripheral, warnings::Warnings};
+use crate::input::dont_care::Patterns;
 
 /// Whole device generation
-pub fn render(d: &Device, config: &Config, device_x: &mut String) -> Result<TokenStream> {
+pub fn render(
+    d: &Device,
+    dont_care: &Patterns,
+    config: &Config,
+    device_x: &mut String,
+    warnings: &mut Warnings,
+) -> Result<TokenStream> {
     let index = svd_parser::expand::Index::create(d);
     let mut out = TokenStream::new();
 
@@ -48,6 +55,12 @@ pub fn render(d: &Device, config: &Config, device_x: &mut String) -> Result<Toke
         });
     }
 
+    if config.target == Target::Avr {
+        out.extend(quote! {
+            #![feature(abi_avr_interrupt)]
+        });
+    }
+
     out.extend(quote! { #![doc = #doc] });
     if !config.make_mod {
         out.extend(quote! {
@@ -160,6 +173,10 @@ pub fn render(d: &Device, config: &Config, device_x: &mut String) -> Result<Toke
                 std::str::from_utf8(include_bytes!("generic_msp430_atomic.rs"))?;
             writeln!(file, "\n{}", msp430_atomic_file)?;
         }
+        if config.target == Target::Avr {
+            let avr_file = std::str::from_utf8(include_bytes!("generic_avr.rs"))?;
+            writeln!(file, "\n{}", avr_file)?;
+        }
         if config.const_generic {
             let array_proxy = std::str::from_utf8(include_bytes!("array_proxy.rs"))?;
             writeln!(file, "{}", array_proxy)?;
@@ -185,6 +202,11 @@ pub fn render(d: &Device, config: &Config, device_x: &mut String) -> Result<Toke
             let generic_msp430_atomic = syn::parse_file(msp430_atomic_file)?.into_token_stream();
             tokens.extend(generic_msp430_atomic);
         }
+        if config.target == Target::Avr {
+            let avr_file = std::str::from_utf8(include_bytes!("generic_avr.rs"))?;
+            let generic_avr = syn::parse_file(avr_file)?.into_token_stream();
+            tokens.extend(generic_avr);
+        }
         if config.const_generic {
             let array_proxy = std::str::from_utf8(include_bytes!("array_proxy.rs"))?;
             let generic_array_proxy = syn::parse_file(array_proxy)?.into_token_stream();
@@ -209,32 +231,49 @@ pub fn render(d: &Device, config: &Config, device_x: &mut String) -> Result<Toke
         config,
     )?);
 
-    for p in &d.peripherals {
-        if config.target == Target::CortexM
-            && core_peripherals.contains(&p.name.to_uppercase().as_ref())
-        {
-            // Core peripherals are handled above
-            continue;
-        }
-
+    let mut peripherals = d
+        .peripherals
+        .iter()
+        // Core peripherals are handled above
+        .filter(|p| {
+            !(config.target == Target::CortexM
+                && core_peripherals.contains(&p.name.to_uppercase().as_ref()))
+        })
+        .collect::<Vec<_>>();
+    let rendered = util::render_each(&mut peripherals, config, |p| {
         debug!("Rendering peripheral {}", p.name);
-        match peripheral::render(p, &index, config) {
-            Ok(periph) => out.extend(periph),
-            Err(e) => {
-                let descrip = p.description.as_deref().unwrap_or("No description");
-                let group_name = p.group_name.as_deref().unwrap_or("No group name");
-                let mut context_string = format!(
-                    "Rendering error at peripheral\nName: {}\nDescription: {descrip}\nGroup: {group_name}",
-                    p.name
-                );
-                if let Some(dname) = p.derived_from.as_ref() {
-                    context_string = format!("{context_string}\nDerived from: {dname}");
-                }
-                let mut e = Err(e);
-                e = e.with_context(|| context_string);
+        let mut p_warnings = Warnings::default();
+        let rendered = peripheral::render(p, &index, dont_care, config, &mut p_warnings).map_err(|e| {
+            if !config.fail_fast {
                 return e;
             }
-        };
+            let descrip = p.description.as_deref().unwrap_or("No description");
+            let group_name = p.group_name.as_deref().unwrap_or("No group name");
+            let mut context_string = format!(
+                "Rendering error at peripheral\nName: {}\nDescription: {descrip}\nGroup: {group_name}",
+                p.name
+            );
+            if let Some(dname) = p.derived_from.as_ref() {
+                context_string = format!("{context_string}\nDerived from: {dname}");
+            }
+            e.context(context_string)
+        });
+        (rendered, p_warnings)
+    })?;
+
+    // The peripherals are parsed back as a whole, in their original order
+    let mut code = String::new();
+    let mut errors = RenderErrors::default();
+    for (p, (rendered, p_warnings)) in peripherals.into_iter().zip(rendered) {
+        warnings.nest(&p.name, p_warnings);
+        match errors.check(&p.name, rendered, config)? {
+            Some(periph) => {
+                code.push_str(&periph);
+                code.push('\n');
+            }
+            // Keep going to report the errors of the other peripherals
+            None => continue,
+        }
 
         if p.registers
             .as_ref()
@@ -248,9 +287,11 @@ pub fn render(d: &Device, config: &Config, device_x: &mut String) -> Result<Toke
             continue;
         }
         let mut feature_attribute = TokenStream::new();
-        if config.feature_group && p.group_name.is_some() {
-            let feature_name = p.group_name.as_ref().unwrap().to_sanitized_snake_case();
-            feature_attribute.extend(quote! { #[cfg(feature = #feature_name)] })
+        if config.feature_group {
+            if let Some(group_name) = p.group_name.as_ref() {
+                let feature_name = group_name.to_sanitized_snake_case();
+                feature_attribute.extend(quote! { #[cfg(feature = #feature_name)] })
+            }
         };
 
         match p {
@@ -298,12 +339,20 @@ pub fn render(d: &Device, config: &Config, device_x: &mut String) -> Result<Toke
             }
         }
     }
+    errors.into_result()?;
+    out.extend(
+        code.parse::<TokenStream>()
+            .map_err(|e| anyhow!("Error parsing rendered peripherals: {:?}", e))?,
+    );
+
+    // avr-device bundles several devices in one crate, so the flag can't be an unmangled symbol
+    let no_mangle = (config.target != Target::Avr).then(|| quote!(#[no_mangle]));
 
     out.extend(quote! {
         // NOTE `no_mangle` is used here to prevent linking different minor versions of the device
         // crate as that would let you `take` the device peripherals more than once (one per minor
         // version)
-        #[no_mangle]
+        #no_mangle
         static mut DEVICE_PERIPHERALS: bool = false;
 
         /// All the peripherals.
@@ -0,0 +1,91 @@
+//! Collection of rendering errors
+//!
+//! Unless [`Config::fail_fast`] is set, rendering goes on past a failing peripheral, register or
+//! field and all errors are reported together, each with the path of the item that failed.
+
+use crate::source::{Location, SourceMap};
+use crate::util::Config;
+use anyhow::Result;
+use std::fmt;
+
+/// Error of a single item
+#[derive(Debug)]
+pub struct RenderError {
+    /// Dot separated path of the item, like `TIM1.CR1.CEN`
+    pub path: String,
+    pub error: anyhow::Error,
+    /// Where the item is in the input, see [`RenderErrors::locate`]
+    pub location: Option<Location>,
+}
+
+/// All errors found while rendering
+#[derive(Debug, Default)]
+pub struct RenderErrors(pub Vec<RenderError>);
+
+impl fmt::Display for RenderErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} rendering error(s)", self.0.len())?;
+        for e in &self.0 {
+            write!(f, "\n{}: {:#}", e.path, e.error)?;
+            if let Some(location) = &e.location {
+                write!(f, "\n{}", location.snippet())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RenderErrors {}
+
+impl RenderErrors {
+    /// Records an error of the item `name`, errors collected inside the item get `name` prepended
+    /// to their paths
+    pub fn push(&mut self, name: &str, error: anyhow::Error) {
+        match error.downcast::<RenderErrors>() {
+            Ok(nested) => self.0.extend(nested.0.into_iter().map(|e| RenderError {
+                path: format!("{}.{}", name, e.path),
+                error: e.error,
+                location: None,
+            })),
+            Err(error) => self.0.push(RenderError {
+                path: name.to_string(),
+                error,
+                location: None,
+            }),
+        }
+    }
+
+    /// Returns the value of a successfully rendered item. On failure returns the error right away
+    /// when failing fast, otherwise records it and returns `None`.
+    pub fn check<T>(
+        &mut self,
+        name: &str,
+        result: Result<T>,
+        config: &Config,
+    ) -> Result<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(error) if config.fail_fast => Err(error),
+            Err(error) => {
+                self.push(name, error);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Finds the failing items in the input the device was loaded from
+    pub fn locate(&mut self, sources: &SourceMap) {
+        for e in &mut self.0 {
+            e.location = sources.locate(&e.path);
+        }
+    }
+
+    /// Fails with the collected errors, if any
+    pub fn into_result(self) -> Result<()> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(self.into())
+        }
+    }
+}
@@ -33,6 +33,22 @@ pub trait Resettable: RegisterSpec {
     fn reset_value() -> Self::Ux;
 }
 
+/// Field metadata, implemented by the zero-sized `<FIELD>_FIELD` type of each field.
+pub trait FieldSpec {
+    /// Register the field is in.
+    type REG: RegisterSpec;
+    /// Raw field type (`bool`, `u8`, `u16`, ...).
+    type Ux: Copy;
+    /// Offset of the lowest bit of the field in the register.
+    const OFFSET: u8;
+    /// Number of bits of the field.
+    const WIDTH: u8;
+    /// Mask of the bits of the field in the register.
+    const MASK: <Self::REG as RegisterSpec>::Ux;
+    /// Reset value of the field, if the register has one.
+    const RESET: Option<Self::Ux>;
+}
+
 /// This structure provides volatile access to registers.
 #[repr(transparent)]
 pub struct Reg<REG: RegisterSpec> {
@@ -348,6 +364,10 @@ impl<FI> BitReader<FI> {
 pub struct Safe;
 #[doc(hidden)]
 pub struct Unsafe;
+#[doc(hidden)]
+pub struct Checked<const MIN: u64, const MAX: u64>;
+#[doc(hidden)]
+pub struct Enumerated;
 
 #[doc(hidden)]
 pub struct FieldWriterRaw<'a, U, REG, N, FI, Safety, const WI: u8, const O: u8>
@@ -405,6 +425,14 @@ where
 pub type FieldWriter<'a, U, REG, N, FI, const WI: u8, const O: u8> = FieldWriterRaw<'a, U, REG, N, FI, Unsafe, WI, O>;
 /// Write field Proxy with safe `bits`
 pub type FieldWriterSafe<'a, U, REG, N, FI, const WI: u8, const O: u8> = FieldWriterRaw<'a, U, REG, N, FI, Safe, WI, O>;
+/// Write field Proxy with unsafe `bits` and a `set` checking the range of values allowed
+pub type FieldWriterChecked<'a, U, REG, N, FI, const WI: u8, const O: u8, const MIN: u64, const MAX: u64> = FieldWriterRaw<'a, U, REG, N, FI, Checked<MIN, MAX>, WI, O>;
+/// Write field Proxy writing only the enumerated values, without `bits`
+pub type FieldWriterEnum<'a, U, REG, N, FI, const WI: u8, const O: u8> = FieldWriterRaw<'a, U, REG, N, FI, Enumerated, WI, O>;
+
+/// Error of a checked write: the value is outside of the range allowed for the field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfRange;
 
 
 impl<'a, U, REG, N, FI, const WI: u8, const OF: u8> FieldWriter<'a, U, REG, N, FI, WI, OF>
@@ -429,6 +457,32 @@ where
     pub const OFFSET: u8 = OF;
 }
 
+impl<'a, U, REG, N, FI, const WI: u8, const OF: u8, const MIN: u64, const MAX: u64> FieldWriterChecked<'a, U, REG, N, FI, WI, OF, MIN, MAX>
+where
+    REG: Writable + RegisterSpec<Ux = U>,
+    FI: Into<N>,
+{
+    /// Field width
+    pub const WIDTH: u8 = WI;
+    /// Field offset
+    pub const OFFSET: u8 = OF;
+    /// Smallest value allowed
+    pub const MIN: u64 = MIN;
+    /// Largest value allowed
+    pub const MAX: u64 = MAX;
+}
+
+impl<'a, U, REG, N, FI, const WI: u8, const OF: u8> FieldWriterEnum<'a, U, REG, N, FI, WI, OF>
+where
+    REG: Writable + RegisterSpec<Ux = U>,
+    FI: Into<N>,
+{
+    /// Field width
+    pub const WIDTH: u8 = WI;
+    /// Field offset
+    pub const OFFSET: u8 = OF;
+}
+
 macro_rules! bit_proxy {
     ($writer:ident, $mwv:ident) => {
         #[doc(hidden)]
@@ -526,6 +580,49 @@ macro_rules! impl_proxy {
                 self.bits(variant.into())
             }
         }
+        impl<'a, REG, N, FI, const WI: u8, const OF: u8, const MIN: u64, const MAX: u64> FieldWriterChecked<'a, $U, REG, N, FI, WI, OF, MIN, MAX>
+        where
+            REG: Writable + RegisterSpec<Ux = $U>,
+            N: Into<$U> + Into<u64> + Copy,
+            FI: Into<N>,
+        {
+            const MASK: $U = <$U>::MAX >> (<$U>::MAX.leading_ones() as u8 - { WI });
+            /// Writes raw bits to the field
+            ///
+            /// # Safety
+            ///
+            /// Passing a value outside of `MIN..=MAX` can cause undefined behaviour. See reference manual
+            #[inline(always)]
+            pub unsafe fn bits(self, value: N) -> &'a mut REG::Writer {
+                self.w.bits =
+                    (self.w.bits & !(Self::MASK << { OF })) | ((Into::<$U>::into(value) & Self::MASK) << { OF });
+                self.w
+            }
+            /// Writes `value` to the field if it is in `MIN..=MAX`
+            #[inline(always)]
+            pub fn set(self, value: N) -> Result<&'a mut REG::Writer, OutOfRange> {
+                if (MIN..=MAX).contains(&Into::<u64>::into(value)) {
+                    Ok(unsafe { self.bits(value) })
+                } else {
+                    Err(OutOfRange)
+                }
+            }
+        }
+        impl<'a, REG, N, FI, const WI: u8, const OF: u8> FieldWriterEnum<'a, $U, REG, N, FI, WI, OF>
+        where
+            REG: Writable + RegisterSpec<Ux = $U>,
+            N: Into<$U>,
+            FI: Into<N>,
+        {
+            const MASK: $U = <$U>::MAX >> (<$U>::MAX.leading_ones() as u8 - { WI });
+            /// Writes `variant` to the field
+            #[inline(always)]
+            pub fn variant(self, variant: FI) -> &'a mut REG::Writer {
+                self.w.bits = (self.w.bits & !(Self::MASK << { OF }))
+                    | ((variant.into().into() & Self::MASK) << { OF });
+                self.w
+            }
+        }
         impl_bit_proxy!(BitWriter, $U);
         impl_bit_proxy!(BitWriter1S, $U);
         impl_bit_proxy!(BitWriter0C, $U);
@@ -0,0 +1,83 @@
+impl<REG: RegisterSpec<Ux = u16>> Reg<REG> {
+    #[inline(always)]
+    fn get_ordered(&self) -> u16 {
+        let ptr = self.register.as_ptr() as *const u8;
+        // SAFETY: the register is two bytes wide. Reading the low byte first latches the high
+        // byte into the shared `TEMP` register.
+        unsafe {
+            let low = ptr.read_volatile();
+            let high = ptr.add(1).read_volatile();
+            u16::from_le_bytes([low, high])
+        }
+    }
+
+    #[inline(always)]
+    fn set_ordered(&self, bits: u16) {
+        let ptr = self.register.as_ptr() as *mut u8;
+        let [low, high] = bits.to_le_bytes();
+        // SAFETY: the register is two bytes wide. Writing the low byte commits the high byte
+        // previously stored into the shared `TEMP` register.
+        unsafe {
+            ptr.add(1).write_volatile(high);
+            ptr.write_volatile(low);
+        }
+    }
+}
+
+impl<REG: Readable + RegisterSpec<Ux = u16>> Reg<REG> {
+    /// Reads a 16-bit register one byte at a time, low byte first.
+    ///
+    /// The 8-bit AVR core accesses 16-bit registers through a `TEMP` register shared by all of
+    /// them, so this must not be interrupted by another 16-bit access.
+    #[inline(always)]
+    pub fn read_ordered(&self) -> REG::Reader {
+        REG::Reader::from(R {
+            bits: self.get_ordered(),
+            _reg: marker::PhantomData,
+        })
+    }
+}
+
+impl<REG: Resettable + Writable + RegisterSpec<Ux = u16>> Reg<REG> {
+    /// Writes a 16-bit register one byte at a time, high byte first.
+    ///
+    /// The 8-bit AVR core accesses 16-bit registers through a `TEMP` register shared by all of
+    /// them, so this must not be interrupted by another 16-bit access.
+    #[inline(always)]
+    pub fn write_ordered<F>(&self, f: F)
+    where
+        F: FnOnce(&mut REG::Writer) -> &mut W<REG>
+    {
+        self.set_ordered(
+            f(&mut REG::Writer::from(W {
+                bits: REG::reset_value(),
+                _reg: marker::PhantomData,
+            }))
+            .bits,
+        );
+    }
+}
+
+impl<REG: Readable + Writable + RegisterSpec<Ux = u16>> Reg<REG> {
+    /// Modifies a 16-bit register using `read_ordered` and `write_ordered` byte orders.
+    #[inline(always)]
+    pub fn modify_ordered<F>(&self, f: F)
+    where
+        for<'w> F: FnOnce(&REG::Reader, &'w mut REG::Writer) -> &'w mut W<REG>
+    {
+        let bits = self.get_ordered();
+        self.set_ordered(
+            f(
+                &REG::Reader::from(R {
+                    bits,
+                    _reg: marker::PhantomData,
+                }),
+                &mut REG::Writer::from(W {
+                    bits,
+                    _reg: marker::PhantomData,
+                }),
+            )
+            .bits,
+        );
+    }
+}
@@ -1,8 +1,8 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::fmt::Write;
 
-use crate::svd::Peripheral;
-use proc_macro2::{Span, TokenStream};
+use crate::svd::{Interrupt, Peripheral};
+use proc_macro2::{Ident, Span, TokenStream};
 use quote::quote;
 
 use crate::util::{self, ToSanitizedCase};
@@ -16,28 +16,30 @@ pub fn render(
     device_x: &mut String,
     config: &Config,
 ) -> Result<TokenStream> {
-    let interrupts = peripherals
-        .iter()
-        .flat_map(|p| {
-            p.interrupt.iter().map(move |i| {
-                (i, p.group_name.clone(), {
-                    match p {
-                        Peripheral::Single(info) => info.name.clone(),
-                        Peripheral::Array(info, dim_element) => {
-                            svd_rs::array::names(info, dim_element).next().unwrap()
-                        }
-                    }
-                })
-            })
-        })
-        .map(|i| (i.0.value, (i.0, i.1, i.2)))
-        .collect::<HashMap<_, _>>();
-
-    let mut interrupts = interrupts.into_iter().map(|(_, v)| v).collect::<Vec<_>>();
-    interrupts.sort_by_key(|i| i.0.value);
+    // Every peripheral declaring a line owns it, the first one in SVD order names it
+    let mut interrupts = BTreeMap::<u32, (&Interrupt, Vec<(Option<&String>, String)>)>::new();
+    for p in peripherals {
+        let names = match p {
+            Peripheral::Single(info) => vec![info.name.clone()],
+            Peripheral::Array(info, dim_element) => {
+                svd_rs::array::names(info, dim_element).collect()
+            }
+        };
+        for i in &p.interrupt {
+            let owners = &mut interrupts.entry(i.value).or_insert((i, Vec::new())).1;
+            for name in &names {
+                let owner = (p.group_name.as_ref(), name.clone());
+                if !owners.contains(&owner) {
+                    owners.push(owner);
+                }
+            }
+        }
+    }
+    let interrupts = interrupts.into_values().collect::<Vec<_>>();
 
     let mut root = TokenStream::new();
     let mut from_arms = TokenStream::new();
+    let mut owner_arms = TokenStream::new();
     let mut elements = TokenStream::new();
     let mut names = vec![];
     let mut names_cfg_attr = vec![];
@@ -54,7 +56,7 @@ pub fn render(
         pos += 1;
 
         let name_constant_case = interrupt.0.name.to_constant_case_ident(Span::call_site());
-        let description = format!(
+        let mut description = format!(
             "{} - {}",
             interrupt.0.value,
             interrupt
@@ -66,25 +68,54 @@ pub fn render(
                 .map(|s| util::escape_brackets(s))
                 .unwrap_or_else(|| interrupt.0.name.clone())
         );
+        if interrupt.1.len() > 1 {
+            let owners = interrupt.1.iter().map(|(_, name)| name.as_str());
+            write!(
+                description,
+                "\n\nShared by {}",
+                owners.collect::<Vec<_>>().join(", ")
+            )?;
+        }
 
         let value = util::unsuffixed(interrupt.0.value.into());
 
-        let mut feature_attribute_flag = false;
-        let mut feature_attribute = TokenStream::new();
-        let mut not_feature_attribute = TokenStream::new();
-        if config.feature_group && interrupt.1.is_some() {
-            let feature_name = interrupt.1.as_ref().unwrap().to_sanitized_snake_case();
-            feature_attribute_flag = true;
-            feature_attribute.extend(quote! { #[cfg(feature = #feature_name)] });
-            not_feature_attribute.extend(quote! { feature = #feature_name, });
-        }
-        if config.feature_peripheral {
-            let feature_name = interrupt.2.to_sanitized_snake_case();
-            feature_attribute_flag = true;
-            feature_attribute.extend(quote! { #[cfg(feature = #feature_name)] });
-            not_feature_attribute.extend(quote! { feature = #feature_name, });
+        // Features enabling each owner, the vector is there when any owner is
+        let mut conditions = Vec::new();
+        for (group_name, name) in &interrupt.1 {
+            let mut features = Vec::new();
+            if config.feature_group {
+                if let Some(group_name) = group_name {
+                    features.push(group_name.to_sanitized_snake_case());
+                }
+            }
+            if config.feature_peripheral {
+                features.push(name.to_sanitized_snake_case());
+            }
+            if features.is_empty() {
+                // An owner that is always there keeps the vector
+                conditions.clear();
+                break;
+            }
+            if !conditions.contains(&features) {
+                conditions.push(features);
+            }
         }
-        let not_feature_attribute = quote! { #[cfg(not(all(#not_feature_attribute)))] };
+
+        let feature_attribute_flag = !conditions.is_empty();
+        let (feature_attribute, not_feature_attribute) = match conditions.as_slice() {
+            [] => (TokenStream::new(), TokenStream::new()),
+            [features] => (
+                quote! { #(#[cfg(feature = #features)])* },
+                quote! { #[cfg(not(all(#(feature = #features,)*)))] },
+            ),
+            _ => {
+                let any = conditions
+                    .iter()
+                    .map(|features| quote! { all(#(feature = #features,)*) });
+                let any = quote! { any(#(#any,)*) };
+                (quote! { #[cfg(#any)] }, quote! { #[cfg(not(#any))] })
+            }
+        };
 
         variants.extend(quote! {
             #[doc = #description]
@@ -97,6 +128,12 @@ pub fn render(
             #value => Ok(Interrupt::#name_constant_case),
         });
 
+        let owners = interrupt.1.iter().map(|(_, name)| name);
+        owner_arms.extend(quote! {
+            #feature_attribute
+            Self::#name_constant_case => &[#(#owners,)*],
+        });
+
         if feature_attribute_flag {
             elements.extend(quote! {
                 #not_feature_attribute
@@ -216,17 +253,73 @@ pub fn render(
                 ];
             });
         }
-        Target::Mips => {}
+        Target::Mips => {
+            // mips-rt jumps from the fixed `.vector_N` stubs to `_vector_N_fn`
+            writeln!(device_x, "PROVIDE(DefaultHandler = _default_isr_fn);")?;
+            for (interrupt, name) in interrupts.iter().zip(&names) {
+                writeln!(device_x, "PROVIDE({} = DefaultHandler);", name)?;
+                writeln!(
+                    device_x,
+                    "PROVIDE(_vector_{}_fn = {});",
+                    interrupt.0.value, name
+                )?;
+            }
+
+            root.extend(quote! {
+                #[cfg(feature = "rt")]
+                extern "C" {
+                    #(#names_cfg_attr fn #names();)*
+                }
+
+                #[doc(hidden)]
+                pub union Vector {
+                    pub _handler: unsafe extern "C" fn(),
+                    _reserved: u32,
+                }
+
+                #[cfg(feature = "rt")]
+                #[doc(hidden)]
+                #[no_mangle]
+                #[used]
+                pub static __INTERRUPTS: [Vector; #n] = [
+                    #elements
+                ];
+            });
+        }
+        Target::Avr => {
+            // avr-libc's startup code owns the vector table and jumps to `__vector_N`
+            for interrupt in &interrupts {
+                writeln!(
+                    device_x,
+                    "PROVIDE(__vector_{} = __vector_default);",
+                    interrupt.0.value
+                )?;
+            }
+        }
         Target::None => {}
     }
 
     let self_token = quote!(self);
     let (enum_repr, nr_expr) = if variants.is_empty() {
         (quote!(), quote!(match #self_token {}))
+    } else if target == Target::Avr {
+        (quote!(#[repr(u8)]), quote!(#self_token as u8))
     } else {
         (quote!(#[repr(u16)]), quote!(#self_token as u16))
     };
 
+    let owners_impl = quote! {
+        impl Interrupt {
+            ///Names of the peripherals declaring the interrupt, in SVD order
+            #[inline]
+            pub fn owners(self) -> &'static [&'static str] {
+                match self {
+                    #owner_arms
+                }
+            }
+        }
+    };
+
     if target == Target::Msp430 {
         let interrupt_enum = quote! {
             ///Enumeration of all the interrupts. This enum is seldom used in application or library crates. It is present primarily for documenting the device's implemented interrupts.
@@ -235,6 +328,8 @@ pub fn render(
             pub enum Interrupt {
                 #variants
             }
+
+            #owners_impl
         };
 
         root.extend(interrupt_enum);
@@ -246,6 +341,8 @@ pub fn render(
             pub enum Interrupt {
                 #variants
             }
+
+            #owners_impl
         };
 
         match target {
@@ -289,6 +386,58 @@ pub fn render(
                     }
                 });
             }
+            Target::Mips => {
+                mod_items.extend(quote! {
+                    #interrupt_enum
+
+                    /// TryFromInterruptError
+                    #[derive(Debug, Copy, Clone)]
+                    pub struct TryFromInterruptError(());
+
+                    impl Interrupt {
+                        /// Returns the vector number of the interrupt
+                        #[inline(always)]
+                        pub fn number(#self_token) -> u16 {
+                            #nr_expr
+                        }
+
+                        /// Attempt to convert a given value into an `Interrupt`
+                        #[inline]
+                        pub fn try_from(value: u16) -> Result<Self, TryFromInterruptError> {
+                            match value {
+                                #from_arms
+                                _ => Err(TryFromInterruptError(())),
+                            }
+                        }
+                    }
+                });
+            }
+            Target::Avr => {
+                mod_items.extend(quote! {
+                    #interrupt_enum
+
+                    /// TryFromInterruptError
+                    #[derive(Debug, Copy, Clone)]
+                    pub struct TryFromInterruptError(());
+
+                    impl Interrupt {
+                        /// Returns the vector number of the interrupt
+                        #[inline(always)]
+                        pub fn number(#self_token) -> u8 {
+                            #nr_expr
+                        }
+
+                        /// Attempt to convert a given value into an `Interrupt`
+                        #[inline]
+                        pub fn try_from(value: u8) -> Result<Self, TryFromInterruptError> {
+                            match value {
+                                #from_arms
+                                _ => Err(TryFromInterruptError(())),
+                            }
+                        }
+                    }
+                });
+            }
             _ => {
                 mod_items.extend(quote! {
                     #interrupt_enum
@@ -313,7 +462,51 @@ pub fn render(
         }
     }
 
-    if target != Target::None {
+    if target == Target::Avr {
+        let arms = interrupts.iter().zip(&names).map(|(interrupt, name)| {
+            let vector = Ident::new(
+                &format!("__vector_{}", interrupt.0.value),
+                Span::call_site(),
+            );
+            quote! {
+                (#name, $path:path) => {
+                    #[no_mangle]
+                    pub unsafe extern "avr-interrupt" fn #vector() {
+                        // check that the handler exists
+                        let _ = $crate::interrupt::Interrupt::#name;
+
+                        // type checking
+                        let f: fn() = $path;
+                        f();
+                    }
+                };
+            }
+        });
+
+        mod_items.extend(quote! {
+            #[cfg(feature = "rt")]
+            #[macro_export]
+            /// Assigns a handler to an interrupt
+            ///
+            /// This macro takes two arguments: the name of an interrupt and the path to the
+            /// function that will be used as the handler of that interrupt. That function
+            /// must have signature `fn()`. The handler is exported as the `__vector_N` symbol
+            /// the avr-libc startup code jumps to.
+            ///
+            /// # Example
+            ///
+            /// ``` ignore
+            /// interrupt!(TIMER0_OVF, tick);
+            ///
+            /// fn tick() {
+            ///     // ...
+            /// }
+            /// ```
+            macro_rules! interrupt {
+                #(#arms)*
+            }
+        });
+    } else if target != Target::None {
         let abi = match target {
             Target::Msp430 => "msp430-interrupt",
             _ => "C",
@@ -323,6 +516,7 @@ pub fn render(
             && target != Target::Msp430
             && target != Target::XtensaLX
             && target != Target::Mips
+            && target != Target::Avr
         {
             mod_items.extend(quote! {
                 #[cfg(feature = "rt")]
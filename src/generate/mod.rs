@@ -1,4 +1,6 @@
 pub mod device;
+pub mod errors;
 pub mod interrupt;
 pub mod peripheral;
 pub mod register;
+pub mod warnings;
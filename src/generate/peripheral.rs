@@ -1,9 +1,12 @@
 use std::borrow::Cow;
 use std::cmp::Ordering;
+use std::collections::HashSet;
 use svd_parser::expand::{derive_cluster, derive_peripheral, derive_register, BlockPath, Index};
 
-use crate::svd::{array::names, Cluster, ClusterInfo, Peripheral, Register, RegisterCluster};
-use log::{debug, trace, warn};
+use crate::svd::{
+    array::names, Cluster, ClusterInfo, DimElement, Peripheral, Register, RegisterCluster,
+};
+use log::{debug, trace};
 use proc_macro2::{Ident, Punct, Spacing, Span, TokenStream};
 use quote::{quote, ToTokens};
 use syn::{punctuated::Punctuated, Token};
@@ -14,9 +17,20 @@ use crate::util::{
 };
 use anyhow::{anyhow, bail, Context, Result};
 
-use crate::generate::register;
+use crate::generate::{
+    errors::RenderErrors,
+    register,
+    warnings::{Code, Warnings},
+};
+use crate::input::dont_care::Patterns;
 
-pub fn render(p_original: &Peripheral, index: &Index, config: &Config) -> Result<TokenStream> {
+pub fn render(
+    p_original: &Peripheral,
+    index: &Index,
+    dont_care: &Patterns,
+    config: &Config,
+    warnings: &mut Warnings,
+) -> Result<TokenStream> {
     let mut out = TokenStream::new();
 
     let mut p = p_original.clone();
@@ -30,7 +44,7 @@ pub fn render(p_original: &Peripheral, index: &Index, config: &Config) -> Result
     let span = Span::call_site();
     let name_str = name.to_sanitized_constant_case();
     let name_constant_case = Ident::new(&name_str, span);
-    let address = util::hex(p.base_address as u64);
+    let address = util::hex(p.base_address);
     let description = util::respace(p.description.as_ref().unwrap_or(&p.name));
 
     let name_snake_case = name.to_snake_case_ident(span);
@@ -40,10 +54,14 @@ pub fn render(p_original: &Peripheral, index: &Index, config: &Config) -> Result
         (false, name_snake_case.clone(), BlockPath::new(&p.name))
     };
 
+    let address_fns = peripheral_address_fns(&p, &path, index, warnings)?;
+
     let mut feature_attribute = TokenStream::new();
-    if config.feature_group && p.group_name.is_some() {
-        let feature_name = p.group_name.as_ref().unwrap().to_sanitized_snake_case();
-        feature_attribute.extend(quote! { #[cfg(feature = #feature_name)] });
+    if config.feature_group {
+        if let Some(group_name) = p.group_name.as_ref() {
+            let feature_name = group_name.to_sanitized_snake_case();
+            feature_attribute.extend(quote! { #[cfg(feature = #feature_name)] });
+        }
     };
 
     match &p {
@@ -64,6 +82,7 @@ pub fn render(p_original: &Peripheral, index: &Index, config: &Config) -> Result
                 };
                 feature_attribute
             });
+            let address_fns_n = vec![&address_fns; names.len()];
             // Insert the peripherals structure
             out.extend(quote! {
                 #(
@@ -76,14 +95,19 @@ pub fn render(p_original: &Peripheral, index: &Index, config: &Config) -> Result
 
                     #feature_attribute_n
                     impl #names_constant_case {
+                        ///Base address of the register block
+                        pub const ADDRESS: usize = #addresses;
+
                         ///Pointer to the register block
-                        pub const PTR: *const #base::RegisterBlock = #addresses as *const _;
+                        pub const PTR: *const #base::RegisterBlock = Self::ADDRESS as *const _;
 
                         ///Return the pointer to the register block
                         #[inline(always)]
                         pub const fn ptr() -> *const #base::RegisterBlock {
                             Self::PTR
                         }
+
+                        #address_fns_n
                     }
 
                     #feature_attribute_n
@@ -105,6 +129,24 @@ pub fn render(p_original: &Peripheral, index: &Index, config: &Config) -> Result
                 )*
             });
 
+            if config.peripheral_arrays {
+                let feature_all_attribute = if config.feature_peripheral {
+                    quote! {#[cfg(all(#(feature = #snake_names),*))]}
+                } else {
+                    quote! {}
+                };
+                out.extend(peripheral_array(
+                    &name_str,
+                    &base,
+                    &names,
+                    (0..dim.dim)
+                        .map(|i| p.base_address + (i * dim.dim_increment) as u64)
+                        .collect(),
+                    &description,
+                    quote! { #feature_attribute #feature_all_attribute },
+                ));
+            }
+
             let feature_any_attribute = quote! {#[cfg(any(#(feature = #snake_names),*))]};
 
             // Derived peripherals may not require re-implementation, and will instead
@@ -135,14 +177,19 @@ pub fn render(p_original: &Peripheral, index: &Index, config: &Config) -> Result
 
                 #feature_attribute
                 impl #name_constant_case {
+                    ///Base address of the register block
+                    pub const ADDRESS: usize = #address;
+
                     ///Pointer to the register block
-                    pub const PTR: *const #base::RegisterBlock = #address as *const _;
+                    pub const PTR: *const #base::RegisterBlock = Self::ADDRESS as *const _;
 
                     ///Return the pointer to the register block
                     #[inline(always)]
                     pub const fn ptr() -> *const #base::RegisterBlock {
                         Self::PTR
                     }
+
+                    #address_fns
                 }
 
                 #feature_attribute
@@ -194,34 +241,269 @@ pub fn render(p_original: &Peripheral, index: &Index, config: &Config) -> Result
     debug!("Pushing cluster & register information into output");
     // Push all cluster & register related information into the peripheral module
 
-    let mod_items = render_ercs(&mut ercs, &path, index, config)?;
+    let mod_items = render_ercs(&mut ercs, &path, index, dont_care, config, warnings)?;
 
     // Push any register or cluster blocks into the output
     debug!(
         "Pushing {} register or cluster blocks into output",
         ercs.len()
     );
-    let reg_block = register_or_cluster_block(&ercs, None, None, config)?;
-
-    let open = Punct::new('{', Spacing::Alone);
-    let close = Punct::new('}', Spacing::Alone);
+    let reg_block = register_or_cluster_block(&ercs, None, None, config, warnings)?;
 
     out.extend(quote! {
         #[doc = #description]
         #feature_attribute
-        pub mod #name_snake_case #open
+        pub mod #name_snake_case {
+            #reg_block
+            #mod_items
+        }
     });
 
-    out.extend(reg_block);
-    out.extend(mod_items);
+    p.registers = Some(ercs);
 
-    close.to_tokens(&mut out);
+    Ok(out)
+}
 
-    p.registers = Some(ercs);
+/// Address of a register in a peripheral, nested clusters included
+struct RegisterAddress {
+    /// Names of the register and of the clusters it is in, `[n]` marking arrays
+    path: Vec<(String, bool)>,
+    /// Offset in the peripheral of the first element of each array
+    offset: u64,
+    /// Increments of the arrays, outermost first
+    increments: Vec<u32>,
+}
+
+fn register_addresses(
+    ercs: &[RegisterCluster],
+    path: &BlockPath,
+    index: &Index,
+    parent: &RegisterAddress,
+    out: &mut Vec<RegisterAddress>,
+) -> Result<()> {
+    for erc in ercs {
+        let (name, address_offset, dim) = match erc {
+            RegisterCluster::Register(r) => (
+                &r.name,
+                r.address_offset,
+                match r {
+                    Register::Single(_) => None,
+                    Register::Array(_, dim) => Some(dim.dim_increment),
+                },
+            ),
+            RegisterCluster::Cluster(c) => (
+                &c.name,
+                c.address_offset,
+                match c {
+                    Cluster::Single(_) => None,
+                    Cluster::Array(_, dim) => Some(dim.dim_increment),
+                },
+            ),
+        };
+        let mut address = RegisterAddress {
+            path: parent.path.clone(),
+            offset: parent.offset + u64::from(address_offset),
+            increments: parent.increments.clone(),
+        };
+        address
+            .path
+            .push((util::replace_suffix(name, ""), dim.is_some()));
+        address.increments.extend(dim);
+        match erc {
+            RegisterCluster::Register(_) => out.push(address),
+            RegisterCluster::Cluster(c) => {
+                // A derived cluster takes the registers of the one it's derived from
+                let mut c = c.clone();
+                if let Some(dpath) = c.derived_from.take() {
+                    derive_cluster(&mut c, &dpath, path, index)?;
+                }
+                let cpath = path.new_cluster(&c.name);
+                register_addresses(&c.children, &cpath, index, &address, out)?;
+            }
+        }
+    }
+    Ok(())
+}
 
+/// `const fn`s of a peripheral computing the addresses of all its registers, like
+/// `txdr_address()` or `ch_cr_address(n)` for the `CR` register of the `CH[n]` cluster array
+fn peripheral_address_fns(
+    p: &Peripheral,
+    path: &BlockPath,
+    index: &Index,
+    warnings: &mut Warnings,
+) -> Result<TokenStream> {
+    let span = Span::call_site();
+    let mut addresses = Vec::new();
+    let top = RegisterAddress {
+        path: Vec::new(),
+        offset: 0,
+        increments: Vec::new(),
+    };
+    register_addresses(
+        p.registers.as_deref().unwrap_or_default(),
+        path,
+        index,
+        &top,
+        &mut addresses,
+    )?;
+
+    let mut out = TokenStream::new();
+    let mut taken = HashSet::new();
+    for address in addresses {
+        let name = address
+            .path
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join("_");
+        let fn_name = format!("{}_address", name.to_sanitized_not_keyword_snake_case());
+        if !taken.insert(fn_name.clone()) {
+            warnings.push(
+                Code::AddressFnConflict,
+                format!("`{fn_name}` is already a method of the peripheral, left out"),
+            );
+            continue;
+        }
+        let fn_name = Ident::new(&fn_name, span);
+
+        // `n` for a single array, `n0`, `n1`, ... from the outermost one for nested arrays
+        let params = (0..address.increments.len())
+            .map(|i| {
+                if address.increments.len() == 1 {
+                    "n".to_string()
+                } else {
+                    format!("n{i}")
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut params_iter = params.iter();
+        let register = address
+            .path
+            .iter()
+            .map(|(name, array)| {
+                if *array {
+                    format!("{name}[{}]", params_iter.next().unwrap())
+                } else {
+                    name.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(".");
+        let doc = format!("Address of the `{register}` register");
+
+        let params = params
+            .iter()
+            .map(|n| Ident::new(n, span))
+            .collect::<Vec<_>>();
+        let mut value = quote! { Self::ADDRESS };
+        if address.offset != 0 {
+            let offset = util::hex(address.offset);
+            value.extend(quote! { + #offset });
+        }
+        for (n, increment) in params.iter().zip(&address.increments) {
+            let increment = util::hex(u64::from(*increment));
+            value.extend(quote! { + #n * #increment });
+        }
+        out.extend(quote! {
+            #[doc = #doc]
+            #[inline(always)]
+            pub const fn #fn_name(#(#params: usize),*) -> usize {
+                #value
+            }
+        });
+    }
     Ok(out)
 }
 
+/// Collection of the instances of a peripheral array, indexed by an enum of their names. It is
+/// built from the instances so that it doesn't alias them. Its name has an `_ARRAY` suffix, as a
+/// peripheral may have the name of the array without its `%s`
+fn peripheral_array(
+    name: &str,
+    base: &Ident,
+    names: &[Cow<str>],
+    addresses: Vec<u64>,
+    description: &str,
+    feature_attribute: TokenStream,
+) -> TokenStream {
+    let span = Span::call_site();
+    let array = format!("{name}_ARRAY");
+    let name_constant_case = Ident::new(&array, span);
+    let index = Ident::new(&format!("{name}_INDEX"), span);
+    let variants = names
+        .iter()
+        .map(|n| Ident::new(&n.to_sanitized_constant_case(), span))
+        .collect::<Vec<_>>();
+    let variant_docs = names.iter().map(|n| format!("Index of `{n}`"));
+    let values = (0..variants.len()).map(|i| unsuffixed(i as _));
+    let len = unsuffixed(names.len() as _);
+    let addresses = addresses.into_iter().map(util::hex);
+    let index_doc = format!("Indexes of the `{name}` instances");
+    quote! {
+        #[doc = #index_doc]
+        #feature_attribute
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum #index {
+            #(#[doc = #variant_docs] #variants = #values,)*
+        }
+
+        #feature_attribute
+        impl #index {
+            ///All the indexes, in array order
+            pub const ALL: [Self; #len] = [#(Self::#variants,)*];
+        }
+
+        #[doc = #description]
+        #feature_attribute
+        pub struct #name_constant_case { _marker: PhantomData<*const ()> }
+
+        #feature_attribute
+        unsafe impl Send for #name_constant_case {}
+
+        #feature_attribute
+        impl #name_constant_case {
+            ///Base addresses of the instances
+            pub const ADDRESSES: [usize; #len] = [#(#addresses,)*];
+
+            ///Takes the instances, which are then reached by index
+            #[inline(always)]
+            pub fn new(_instances: (#(#variants,)*)) -> Self {
+                Self { _marker: PhantomData }
+            }
+
+            ///Gives the instances back
+            #[inline(always)]
+            pub fn free(self) -> (#(#variants,)*) {
+                (#(#variants { _marker: PhantomData },)*)
+            }
+
+            ///Return the pointer to the register block of an instance
+            #[inline(always)]
+            pub const fn ptr(index: #index) -> *const #base::RegisterBlock {
+                Self::ADDRESSES[index as usize] as *const _
+            }
+        }
+
+        #feature_attribute
+        impl core::ops::Index<#index> for #name_constant_case {
+            type Output = #base::RegisterBlock;
+
+            #[inline(always)]
+            fn index(&self, index: #index) -> &Self::Output {
+                unsafe { &*Self::ptr(index) }
+            }
+        }
+
+        #feature_attribute
+        impl core::fmt::Debug for #name_constant_case {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                f.debug_struct(#array).finish()
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ArrayAccessor {
     pub doc: String,
@@ -249,6 +531,81 @@ impl ArrayAccessor {
     }
 }
 
+/// Iterator over the elements of an array expanded to separately named fields
+#[derive(Clone, Debug)]
+pub struct ArrayIter {
+    pub doc: String,
+    pub name: Ident,
+    pub ty: syn::Type,
+    pub elements: Vec<Ident>,
+}
+
+impl ArrayIter {
+    /// `methods` are the elements in unions, accessed with methods
+    pub fn to_tokens(&self, methods: &HashSet<Ident>) -> TokenStream {
+        let doc = &self.doc;
+        let name = &self.name;
+        let ty = &self.ty;
+        let elements = self.elements.iter().map(|e| {
+            if methods.contains(e) {
+                quote! { self.#e() }
+            } else {
+                quote! { &self.#e }
+            }
+        });
+        quote! {
+            #[doc = #doc]
+            #[inline(always)]
+            pub fn #name(&self) -> impl Iterator<Item = &#ty> {
+                IntoIterator::into_iter([#(#elements,)*])
+            }
+        }
+    }
+}
+
+/// Enum of the `dimIndex` names of an array, indexing it
+#[derive(Clone, Debug)]
+pub struct IndexEnum {
+    pub doc: String,
+    pub name: Ident,
+    pub variants: Vec<Ident>,
+    pub variant_docs: Vec<String>,
+    /// Type of the array field and of its elements
+    pub array_ty: syn::Type,
+    pub ty: syn::Type,
+}
+
+impl IndexEnum {
+    pub fn to_tokens(&self) -> TokenStream {
+        let doc = &self.doc;
+        let name = &self.name;
+        let variants = &self.variants;
+        let variant_docs = &self.variant_docs;
+        let values = (0..variants.len()).map(|i| unsuffixed(i as _));
+        let len = unsuffixed(variants.len() as _);
+        let array_ty = &self.array_ty;
+        let ty = &self.ty;
+        quote! {
+            #[doc = #doc]
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            pub enum #name {
+                #(#[doc = #variant_docs] #variants = #values,)*
+            }
+            impl #name {
+                ///All the indexes, in array order
+                pub const ALL: [Self; #len] = [#(Self::#variants,)*];
+            }
+            impl core::ops::Index<#name> for #array_ty {
+                type Output = #ty;
+                #[inline(always)]
+                fn index(&self, index: #name) -> &#ty {
+                    &self[index as usize]
+                }
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 struct RegisterBlockField {
     syn_field: syn::Field,
@@ -256,6 +613,48 @@ struct RegisterBlockField {
     offset: u32,
     size: u32,
     accessors: Vec<ArrayAccessor>,
+    iter: Option<ArrayIter>,
+    index: Option<IndexEnum>,
+    /// Distance between the elements of array fields, in bytes
+    increment: Option<u32>,
+}
+
+impl RegisterBlockField {
+    /// Name of the `const fn` computing the address of the field from the address of the block,
+    /// and the function
+    fn address_fn(&self) -> (Ident, TokenStream) {
+        let span = Span::call_site();
+        let name = self.syn_field.ident.as_ref().unwrap().to_string();
+        let name = name.trim_start_matches("r#");
+        let fn_name = Ident::new(&format!("{name}_address"), span);
+        let address = if self.offset == 0 {
+            quote! { base }
+        } else {
+            let offset = util::hex(self.offset as u64);
+            quote! { base + #offset }
+        };
+        let address_fn = if let Some(increment) = self.increment {
+            let doc = format!("Address of element `n` of `{name}` in the block at `base`");
+            let increment = util::hex(increment as u64);
+            quote! {
+                #[doc = #doc]
+                #[inline(always)]
+                pub const fn #fn_name(base: usize, n: usize) -> usize {
+                    #address + n * #increment
+                }
+            }
+        } else {
+            let doc = format!("Address of `{name}` in the block at `base`");
+            quote! {
+                #[doc = #doc]
+                #[inline(always)]
+                pub const fn #fn_name(base: usize) -> usize {
+                    #address
+                }
+            }
+        };
+        (fn_name, address_fn)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -423,10 +822,11 @@ impl FieldRegions {
 
         new_region.rbfs.sort_by_key(|f| f.offset);
 
-        // maintain the regions ordered by starting offset
+        // maintain the regions ordered by starting offset, zero-sized
+        // regions (`ArrayProxy`) before the ones starting at the same offset
         let idx = self
             .regions
-            .binary_search_by_key(&new_region.offset, |r| r.offset);
+            .binary_search_by_key(&(new_region.offset, new_region.end), |r| (r.offset, r.end));
         match idx {
             Ok(idx) => {
                 bail!(
@@ -443,7 +843,7 @@ impl FieldRegions {
     }
 
     /// Resolves type name conflicts
-    pub fn resolve_idents(&mut self) -> Result<()> {
+    pub fn resolve_idents(&mut self, warnings: &mut Warnings) -> Result<()> {
         let idents: Vec<_> = {
             self.regions
                 .iter_mut()
@@ -462,9 +862,13 @@ impl FieldRegions {
             })
             .for_each(|r| {
                 let new_ident = r.shortest_ident();
-                warn!(
-                    "Found type name conflict with region {:?}, renamed to {new_ident:?}",
-                    r.ident
+                warnings.push(
+                    Code::TypeNameConflict,
+                    format!(
+                        "union type name `{}` conflicts with another union, renamed to `{}`",
+                        r.ident.as_deref().unwrap_or_default(),
+                        new_ident.as_deref().unwrap_or_default()
+                    ),
                 );
                 r.ident = new_ident;
             });
@@ -487,9 +891,17 @@ fn register_or_cluster_block(
     name: Option<&str>,
     size: Option<u32>,
     config: &Config,
+    warnings: &mut Warnings,
 ) -> Result<TokenStream> {
     let mut rbfs = TokenStream::new();
     let mut accessors = TokenStream::new();
+    // Fields in unions, accessed with methods, and iterators over arrays of fields
+    let mut methods = HashSet::new();
+    let mut iters = Vec::new();
+    let mut accessor_names = HashSet::new();
+    let mut address_fns = Vec::new();
+    // Enums of the names indexing arrays
+    let mut index_enums = TokenStream::new();
 
     let ercs_expanded =
         expand(ercs, config).with_context(|| "Could not expand register or cluster block")?;
@@ -502,7 +914,7 @@ fn register_or_cluster_block(
     }
 
     // We need to compute the idents of each register/union block first to make sure no conflicts exists.
-    regions.resolve_idents()?;
+    regions.resolve_idents(warnings)?;
     // The end of the region for which we previously emitted a rbf into `rbfs`
     let mut last_end = 0;
 
@@ -528,10 +940,13 @@ fn register_or_cluster_block(
                 &reg_block_field.description,
             );
 
+            iters.extend(&reg_block_field.iter);
+            index_enums.extend(reg_block_field.index.as_ref().map(IndexEnum::to_tokens));
             if is_region_a_union {
                 let name = &reg_block_field.syn_field.ident;
                 let ty = &reg_block_field.syn_field.ty;
                 let offset = reg_block_field.offset as usize;
+                methods.extend(name.clone());
                 accessors.extend(quote! {
                     #[doc = #comment]
                     #[inline(always)]
@@ -555,6 +970,8 @@ fn register_or_cluster_block(
                     .iter()
                     .map(|a| a.to_tokens(is_region_a_union)),
             );
+            accessor_names.extend(reg_block_field.accessors.iter().map(|a| a.name.clone()));
+            address_fns.push(reg_block_field.address_fn());
         }
 
         if !is_region_a_union {
@@ -602,6 +1019,23 @@ fn register_or_cluster_block(
         }
     }
 
+    accessors.extend(iters.iter().map(|iter| iter.to_tokens(&methods)));
+
+    // The address of `FOO` must not take the name of the accessor of a `FOO_ADDRESS` register
+    let mut taken = methods;
+    taken.extend(accessor_names);
+    taken.extend(iters.iter().map(|iter| iter.name.clone()));
+    for (fn_name, address_fn) in address_fns {
+        if taken.insert(fn_name.clone()) {
+            accessors.extend(address_fn);
+        } else {
+            warnings.push(
+                Code::AddressFnConflict,
+                format!("`{fn_name}` is already a method of the register block, left out"),
+            );
+        }
+    }
+
     let name = if let Some(name) = name {
         name.to_constant_case_ident(span)
     } else {
@@ -626,6 +1060,8 @@ fn register_or_cluster_block(
         }
 
         #accessors
+
+        #index_enums
     })
 }
 
@@ -635,28 +1071,46 @@ fn expand(ercs: &[RegisterCluster], config: &Config) -> Result<Vec<RegisterBlock
     let mut ercs_expanded = vec![];
 
     debug!("Expanding registers or clusters into Register Block Fields");
+    let mut errors = RenderErrors::default();
     for erc in ercs {
         match &erc {
             RegisterCluster::Register(register) => {
                 let reg_name = &register.name;
-                let expanded_reg = expand_register(register, config).with_context(|| {
-                    let descrip = register.description.as_deref().unwrap_or("No description");
-                    format!("Error expanding register\nName: {reg_name}\nDescription: {descrip}")
-                })?;
+                let mut expanded_reg = expand_register(register, config);
+                if config.fail_fast {
+                    expanded_reg = expanded_reg.with_context(|| {
+                        let descrip = register.description.as_deref().unwrap_or("No description");
+                        format!(
+                            "Error expanding register\nName: {reg_name}\nDescription: {descrip}"
+                        )
+                    });
+                }
                 trace!("Register: {reg_name}");
-                ercs_expanded.extend(expanded_reg);
+                if let Some(expanded_reg) = errors.check(reg_name, expanded_reg, config)? {
+                    ercs_expanded.extend(expanded_reg);
+                }
             }
             RegisterCluster::Cluster(cluster) => {
                 let cluster_name = &cluster.name;
-                let expanded_cluster = expand_cluster(cluster, config).with_context(|| {
-                    let descrip = cluster.description.as_deref().unwrap_or("No description");
-                    format!("Error expanding cluster\nName: {cluster_name}\nDescription: {descrip}")
-                })?;
+                let mut expanded_cluster = expand_cluster(cluster, config);
+                if config.fail_fast {
+                    expanded_cluster = expanded_cluster.with_context(|| {
+                        let descrip = cluster.description.as_deref().unwrap_or("No description");
+                        format!(
+                            "Error expanding cluster\nName: {cluster_name}\nDescription: {descrip}"
+                        )
+                    });
+                }
                 trace!("Cluster: {cluster_name}");
-                ercs_expanded.extend(expanded_cluster);
+                if let Some(expanded_cluster) =
+                    errors.check(cluster_name, expanded_cluster, config)?
+                {
+                    ercs_expanded.extend(expanded_cluster);
+                }
             }
         };
     }
+    errors.into_result()?;
 
     ercs_expanded.sort_by_key(|x| x.offset);
 
@@ -737,6 +1191,9 @@ fn expand_cluster(cluster: &Cluster, config: &Config) -> Result<Vec<RegisterBloc
                 offset: info.address_offset,
                 size: cluster_size,
                 accessors: Vec::new(),
+                iter: None,
+                index: None,
+                increment: None,
             })
         }
         Cluster::Array(info, array_info) => {
@@ -767,6 +1224,9 @@ fn expand_cluster(cluster: &Cluster, config: &Config) -> Result<Vec<RegisterBloc
             };
 
             let array_convertible = sequential_addresses && convert_list;
+            // arrays indexed by names get an enum of the names
+            let named =
+                config.index_enums && convert_list && array_info.indexes_as_range().is_none();
 
             if array_convertible {
                 let accessors = if sequential_indexes_from0 {
@@ -794,7 +1254,9 @@ fn expand_cluster(cluster: &Cluster, config: &Config) -> Result<Vec<RegisterBloc
                     }
                     accessors
                 };
-                let array_ty = new_syn_array(ty, array_info.dim);
+                let array_ty = new_syn_array(ty.clone(), array_info.dim);
+                let index =
+                    named.then(|| index_enum(&info.name, &ty_name, &ty, &array_ty, array_info));
                 cluster_expanded.push(RegisterBlockField {
                     syn_field: new_syn_field(
                         ty_name.to_snake_case_ident(Span::call_site()),
@@ -804,21 +1266,46 @@ fn expand_cluster(cluster: &Cluster, config: &Config) -> Result<Vec<RegisterBloc
                     offset: info.address_offset,
                     size: cluster_size * array_info.dim,
                     accessors,
+                    iter: None,
+                    index,
+                    increment: Some(array_info.dim_increment),
                 });
-            } else if sequential_indexes_from0 && config.const_generic {
+            } else if (sequential_indexes_from0 || named) && config.const_generic {
                 // Include a ZST ArrayProxy giving indexed access to the
                 // elements.
-                let ap_path = array_proxy_type(ty, array_info);
-                let syn_field =
-                    new_syn_field(ty_name.to_snake_case_ident(Span::call_site()), ap_path);
+                let ap_path = array_proxy_type(ty.clone(), array_info);
+                let syn_field = new_syn_field(
+                    ty_name.to_snake_case_ident(Span::call_site()),
+                    ap_path.clone(),
+                );
+                let (accessors, index) = if named {
+                    (
+                        proxy_accessors(
+                            &info.name,
+                            &ty_name,
+                            &ty,
+                            array_info,
+                            cluster_size,
+                            info.address_offset,
+                            &description,
+                        ),
+                        Some(index_enum(&info.name, &ty_name, &ty, &ap_path, array_info)),
+                    )
+                } else {
+                    (Vec::new(), None)
+                };
                 cluster_expanded.push(RegisterBlockField {
                     syn_field,
                     description: info.description.as_ref().unwrap_or(&info.name).into(),
                     offset: info.address_offset,
                     size: 0,
-                    accessors: Vec::new(),
+                    accessors,
+                    iter: None,
+                    index,
+                    increment: Some(array_info.dim_increment),
                 });
             } else {
+                let iter = array_iter(&info.name, &ty_name, &ty, array_info);
                 for (field_num, idx) in array_info.indexes().enumerate() {
                     let nb_name = util::replace_suffix(&info.name, &idx);
                     let syn_field =
@@ -830,6 +1317,13 @@ fn expand_cluster(cluster: &Cluster, config: &Config) -> Result<Vec<RegisterBloc
                         offset: info.address_offset + field_num as u32 * array_info.dim_increment,
                         size: cluster_size,
                         accessors: Vec::new(),
+                        iter: if field_num == 0 {
+                            Some(iter.clone())
+                        } else {
+                            None
+                        },
+                        index: None,
+                        increment: None,
                     });
                 }
             }
@@ -867,6 +1361,9 @@ fn expand_register(register: &Register, config: &Config) -> Result<Vec<RegisterB
                 offset: info.address_offset,
                 size: register_size,
                 accessors: Vec::new(),
+                iter: None,
+                index: None,
+                increment: None,
             })
         }
         Register::Array(info, array_info) => {
@@ -882,6 +1379,9 @@ fn expand_register(register: &Register, config: &Config) -> Result<Vec<RegisterB
             };
 
             let array_convertible = sequential_addresses && convert_list;
+            // arrays indexed by names get an enum of the names
+            let named =
+                config.index_enums && convert_list && array_info.indexes_as_range().is_none();
 
             if array_convertible {
                 // if dimIndex exists, test if it is a sequence of numbers from 0 to dim
@@ -915,7 +1415,9 @@ fn expand_register(register: &Register, config: &Config) -> Result<Vec<RegisterB
                     }
                     accessors
                 };
-                let array_ty = new_syn_array(ty, array_info.dim);
+                let array_ty = new_syn_array(ty.clone(), array_info.dim);
+                let index =
+                    named.then(|| index_enum(&info_name, &ty_name, &ty, &array_ty, array_info));
                 let syn_field =
                     new_syn_field(ty_name.to_snake_case_ident(Span::call_site()), array_ty);
                 register_expanded.push(RegisterBlockField {
@@ -924,8 +1426,38 @@ fn expand_register(register: &Register, config: &Config) -> Result<Vec<RegisterB
                     offset: info.address_offset,
                     size: register_size * array_info.dim,
                     accessors,
+                    iter: None,
+                    index,
+                    increment: Some(array_info.dim_increment),
+                });
+            } else if named && config.const_generic {
+                // Include a ZST ArrayProxy giving access to the elements by name
+                let ap_path = array_proxy_type(ty.clone(), array_info);
+                let syn_field = new_syn_field(
+                    ty_name.to_snake_case_ident(Span::call_site()),
+                    ap_path.clone(),
+                );
+                let accessors = proxy_accessors(
+                    &info_name,
+                    &ty_name,
+                    &ty,
+                    array_info,
+                    register_size,
+                    info.address_offset,
+                    &description,
+                );
+                register_expanded.push(RegisterBlockField {
+                    syn_field,
+                    description,
+                    offset: info.address_offset,
+                    size: 0,
+                    accessors,
+                    iter: None,
+                    index: Some(index_enum(&info_name, &ty_name, &ty, &ap_path, array_info)),
+                    increment: Some(array_info.dim_increment),
                 });
             } else {
+                let iter = array_iter(&info_name, &ty_name, &ty, array_info);
                 for (field_num, idx) in array_info.indexes().enumerate() {
                     let nb_name = util::replace_suffix(&info_name, &idx);
                     let syn_field =
@@ -937,6 +1469,13 @@ fn expand_register(register: &Register, config: &Config) -> Result<Vec<RegisterB
                         offset: info.address_offset + field_num as u32 * array_info.dim_increment,
                         size: register_size,
                         accessors: Vec::new(),
+                        iter: if field_num == 0 {
+                            Some(iter.clone())
+                        } else {
+                            None
+                        },
+                        index: None,
+                        increment: None,
                     });
                 }
             }
@@ -946,48 +1485,136 @@ fn expand_register(register: &Register, config: &Config) -> Result<Vec<RegisterB
     Ok(register_expanded)
 }
 
+/// Iterator over the elements of an array that could not be converted to a Rust array
+fn array_iter(name: &str, ty_name: &str, ty: &syn::Type, array_info: &DimElement) -> ArrayIter {
+    let span = Span::call_site();
+    ArrayIter {
+        doc: format!("Iterator over the elements of `{ty_name}`"),
+        name: Ident::new(&format!("{}_iter", ty_name.to_snake_case_ident(span)), span),
+        ty: ty.clone(),
+        elements: array_info
+            .indexes()
+            .map(|idx| util::replace_suffix(name, &idx).to_snake_case_ident(span))
+            .collect(),
+    }
+}
+
+/// Enum of the `dimIndex` names of an array, indexing `array_ty`
+fn index_enum(
+    name: &str,
+    ty_name: &str,
+    ty: &syn::Type,
+    array_ty: &syn::Type,
+    array_info: &DimElement,
+) -> IndexEnum {
+    let span = Span::call_site();
+    IndexEnum {
+        doc: format!("Indexes of `{ty_name}`"),
+        name: format!("{ty_name}_INDEX").to_constant_case_ident(span),
+        variants: array_info
+            .indexes()
+            .map(|idx| idx.to_constant_case_ident(span))
+            .collect(),
+        variant_docs: array_info
+            .indexes()
+            .map(|idx| format!("Index of `{}`", util::replace_suffix(name, &idx)))
+            .collect(),
+        array_ty: array_ty.clone(),
+        ty: ty.clone(),
+    }
+}
+
+/// Accessors by name to the elements of an `ArrayProxy`
+fn proxy_accessors(
+    name: &str,
+    ty_name: &str,
+    ty: &syn::Type,
+    array_info: &DimElement,
+    size: u32,
+    offset: u32,
+    description: &str,
+) -> Vec<ArrayAccessor> {
+    let span = Span::call_site();
+    let basename = ty_name.to_snake_case_ident(span);
+    array_info
+        .indexes()
+        .enumerate()
+        .map(|(i, idx)| ArrayAccessor {
+            doc: make_comment(
+                size,
+                offset + array_info.dim_increment * i as u32,
+                description,
+            ),
+            name: util::replace_suffix(name, &idx).to_snake_case_ident(span),
+            ty: ty.clone(),
+            basename: basename.clone(),
+            i: unsuffixed(i as _),
+        })
+        .collect()
+}
+
 fn render_ercs(
     ercs: &mut [RegisterCluster],
     path: &BlockPath,
     index: &Index,
+    dont_care: &Patterns,
     config: &Config,
+    warnings: &mut Warnings,
 ) -> Result<TokenStream> {
     let mut mod_items = TokenStream::new();
+    let mut errors = RenderErrors::default();
 
     for erc in ercs {
         match erc {
             // Generate the sub-cluster blocks.
             RegisterCluster::Cluster(c) => {
                 trace!("Cluster: {}", c.name);
-                let mut cpath = None;
-                let dpath = c.derived_from.take();
-                if let Some(dpath) = dpath {
-                    cpath = derive_cluster(c, &dpath, path, index)?;
+                let mut c_warnings = Warnings::default();
+                let rendered = (|| {
+                    let mut cpath = None;
+                    let dpath = c.derived_from.take();
+                    if let Some(dpath) = dpath {
+                        cpath = derive_cluster(c, &dpath, path, index)?;
+                    }
+                    cluster_block(c, path, cpath, index, dont_care, config, &mut c_warnings)
+                })();
+                warnings.nest(&c.name, c_warnings);
+                if let Some(rendered) = errors.check(&c.name, rendered, config)? {
+                    mod_items.extend(rendered);
                 }
-                mod_items.extend(cluster_block(c, path, cpath, index, config)?);
             }
 
             // Generate definition for each of the registers.
             RegisterCluster::Register(reg) => {
                 trace!("Register: {}", reg.name);
-                let mut rpath = None;
-                let dpath = reg.derived_from.take();
-                if let Some(dpath) = dpath {
-                    rpath = derive_register(reg, &dpath, path, index)?;
-                }
-                let reg_name = &reg.name;
-
-                let rendered_reg =
-                    register::render(reg, path, rpath, index, config).with_context(|| {
+                let mut r_warnings = Warnings::default();
+                let rendered = (|| {
+                    let mut rpath = None;
+                    let dpath = reg.derived_from.take();
+                    if let Some(dpath) = dpath {
+                        rpath = derive_register(reg, &dpath, path, index)?;
+                    }
+                    register::render(reg, path, rpath, index, dont_care, config, &mut r_warnings)
+                })();
+                warnings.nest(&reg.name, r_warnings);
+                let rendered = if config.fail_fast {
+                    rendered.with_context(|| {
                         let descrip = reg.description.as_deref().unwrap_or("No description");
                         format!(
-                            "Error rendering register\nName: {reg_name}\nDescription: {descrip}"
+                            "Error rendering register\nName: {}\nDescription: {descrip}",
+                            reg.name
                         )
-                    })?;
-                mod_items.extend(rendered_reg)
+                    })
+                } else {
+                    rendered
+                };
+                if let Some(rendered) = errors.check(&reg.name, rendered, config)? {
+                    mod_items.extend(rendered);
+                }
             }
         }
     }
+    errors.into_result()?;
     Ok(mod_items)
 }
 
@@ -997,7 +1624,9 @@ fn cluster_block(
     path: &BlockPath,
     dpath: Option<BlockPath>,
     index: &Index,
+    dont_care: &Patterns,
     config: &Config,
+    warnings: &mut Warnings,
 ) -> Result<TokenStream> {
     let description =
         util::escape_brackets(&util::respace(c.description.as_ref().unwrap_or(&c.name)));
@@ -1033,7 +1662,7 @@ fn cluster_block(
         })
     } else {
         let cpath = path.new_cluster(&c.name);
-        let mod_items = render_ercs(&mut c.children, &cpath, index, config)?;
+        let mod_items = render_ercs(&mut c.children, &cpath, index, dont_care, config, warnings)?;
 
         // Generate the register block.
         let cluster_size = match c {
@@ -1042,8 +1671,13 @@ fn cluster_block(
             }
             _ => None,
         };
-        let reg_block =
-            register_or_cluster_block(&c.children, Some(&mod_name), cluster_size, config)?;
+        let reg_block = register_or_cluster_block(
+            &c.children,
+            Some(&mod_name),
+            cluster_size,
+            config,
+            warnings,
+        )?;
 
         let mod_items = quote! {
             #reg_block
@@ -1,16 +1,17 @@
 use crate::svd::{
-    Access, BitRange, EnumeratedValues, Field, ModifiedWriteValues, ReadAction, Register,
-    RegisterProperties, Usage, WriteConstraint,
+    Access, BitRange, EnumeratedValue, EnumeratedValues, Field, ModifiedWriteValues, ReadAction,
+    Register, RegisterProperties, Usage, WriteConstraint,
 };
-use core::u64;
-use log::warn;
-use proc_macro2::{Ident, Punct, Spacing, Span, TokenStream};
+use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
 use std::collections::HashSet;
 use svd_parser::expand::{
     derive_enumerated_values, derive_field, BlockPath, EnumPath, FieldPath, Index, RegisterPath,
 };
 
+use crate::generate::errors::RenderErrors;
+use crate::generate::warnings::{Code, Warnings};
+use crate::input::dont_care::Patterns;
 use crate::util::{self, ident_to_path, path_segment, type_path, Config, ToSanitizedCase, U32Ext};
 use anyhow::{anyhow, Result};
 use syn::punctuated::Punctuated;
@@ -20,7 +21,9 @@ pub fn render(
     path: &BlockPath,
     dpath: Option<RegisterPath>,
     index: &Index,
+    dont_care: &Patterns,
     config: &Config,
+    warnings: &mut Warnings,
 ) -> Result<TokenStream> {
     let name = util::name_of(register, config.ignore_groups);
     let span = Span::call_site();
@@ -28,7 +31,10 @@ pub fn render(
     let name_snake_case = name.to_snake_case_ident(span);
     let description = util::escape_brackets(
         util::respace(&register.description.clone().unwrap_or_else(|| {
-            warn!("Missing description for register {}", register.name);
+            warnings.push(
+                Code::MissingDescription,
+                "register has no description".to_string(),
+            );
             Default::default()
         }))
         .as_ref(),
@@ -80,6 +86,7 @@ pub fn render(
             access,
             &path.new_register(&register.name),
             index,
+            dont_care,
             config,
         )?;
 
@@ -99,6 +106,7 @@ pub fn render_register_mod(
     access: Access,
     path: &RegisterPath,
     index: &Index,
+    dont_care: &Patterns,
     config: &Config,
 ) -> Result<TokenStream> {
     let properties = &register.properties;
@@ -118,11 +126,8 @@ pub fn render_register_mod(
     };
     let rty = rsize.to_ty()?;
     let description = util::escape_brackets(
-        util::respace(&register.description.clone().unwrap_or_else(|| {
-            warn!("Missing description for register {}", register.name);
-            Default::default()
-        }))
-        .as_ref(),
+        // Reported by `render`
+        util::respace(&register.description.clone().unwrap_or_default()).as_ref(),
     );
 
     let mut mod_items = TokenStream::new();
@@ -233,6 +238,7 @@ pub fn render_register_mod(
                 register,
                 path,
                 index,
+                dont_care,
                 &name_constant_case_spec,
                 &rty,
                 access,
@@ -245,22 +251,13 @@ pub fn render_register_mod(
         }
     }
 
-    let open = Punct::new('{', Spacing::Alone);
-    let close = Punct::new('}', Spacing::Alone);
-
     if can_read && !r_impl_items.is_empty() {
         mod_items.extend(quote! {
-            impl R #open #r_impl_items #close
+            impl R { #r_impl_items }
         });
     }
 
     if can_write {
-        mod_items.extend(quote! {
-            impl W #open
-        });
-
-        mod_items.extend(w_impl_items);
-
         // the writer can be safe if:
         // * there is a single field that covers the entire register
         // * that field can represent all values
@@ -276,7 +273,7 @@ pub fn render_register_mod(
         ) || !unsafety(register.write_constraint.as_ref(), rsize);
 
         if can_write_safe {
-            mod_items.extend(quote! {
+            w_impl_items.extend(quote! {
                 #[doc = "Writes raw bits to the register."]
                 #[inline(always)]
                 pub fn bits(&mut self, bits: #rty) -> &mut Self {
@@ -285,7 +282,7 @@ pub fn render_register_mod(
                 }
             });
         } else {
-            mod_items.extend(quote! {
+            w_impl_items.extend(quote! {
                 #[doc = "Writes raw bits to the register."]
                 #[inline(always)]
                 pub unsafe fn bits(&mut self, bits: #rty) -> &mut Self {
@@ -295,7 +292,9 @@ pub fn render_register_mod(
             });
         }
 
-        close.to_tokens(&mut mod_items);
+        mod_items.extend(quote! {
+            impl W { #w_impl_items }
+        });
     }
 
     let methods = methods
@@ -322,10 +321,21 @@ pub fn render_register_mod(
         }
     }
 
+    let offset = util::hex(register.address_offset as u64);
+    let offset_doc = if register.is_single() {
+        "Offset of the register in its register block"
+    } else {
+        "Offset of the first register of the array in its register block"
+    };
     mod_items.extend(quote! {
         #[doc = #doc]
         pub struct #name_constant_case_spec;
 
+        impl #name_constant_case_spec {
+            #[doc = #offset_doc]
+            pub const OFFSET: u32 = #offset;
+        }
+
         impl crate::RegisterSpec for #name_constant_case_spec {
             type Ux = #rty;
         }
@@ -370,6 +380,7 @@ pub fn fields(
     register: &Register,
     rpath: &RegisterPath,
     index: &Index,
+    dont_care: &Patterns,
     name_constant_case_spec: &Ident,
     rty: &Ident,
     access: Access,
@@ -379,595 +390,886 @@ pub fn fields(
     w_impl_items: &mut TokenStream,
     config: &Config,
 ) -> Result<()> {
-    let span = Span::call_site();
-    let can_read = access.can_read();
-    let can_write = access.can_write();
-
     fields.sort_by_key(|f| f.bit_offset());
 
     // Hack for #625
-    let mut enum_derives = HashSet::new();
-    let mut reader_derives = HashSet::new();
-    let mut writer_enum_derives = HashSet::new();
-    let mut writer_derives = HashSet::new();
+    let mut derives = Derives::default();
+
+    let mut errors = RenderErrors::default();
+    for &f in fields.iter() {
+        let rendered = field(
+            f,
+            register,
+            rpath,
+            index,
+            dont_care,
+            name_constant_case_spec,
+            rty,
+            access,
+            properties,
+            mod_items,
+            r_impl_items,
+            w_impl_items,
+            &mut derives,
+            config,
+        );
+        errors.check(&f.name, rendered, config)?;
+    }
+
+    errors.into_result()
+}
+
+/// Types already defined for a previous field, see #625
+#[derive(Default)]
+struct Derives {
+    enum_derives: HashSet<Ident>,
+    reader_derives: HashSet<Ident>,
+    writer_enum_derives: HashSet<Ident>,
+    writer_derives: HashSet<Ident>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn field(
+    f: &Field,
+    register: &Register,
+    rpath: &RegisterPath,
+    index: &Index,
+    dont_care: &Patterns,
+    name_constant_case_spec: &Ident,
+    rty: &Ident,
+    access: Access,
+    properties: &RegisterProperties,
+    mod_items: &mut TokenStream,
+    r_impl_items: &mut TokenStream,
+    w_impl_items: &mut TokenStream,
+    derives: &mut Derives,
+    config: &Config,
+) -> Result<()> {
+    let span = Span::call_site();
+    let can_read = access.can_read();
+    let can_write = access.can_write();
+    let Derives {
+        enum_derives,
+        reader_derives,
+        writer_enum_derives,
+        writer_derives,
+    } = derives;
 
     // TODO enumeratedValues
     let inline = quote! { #[inline(always)] };
-    for &f in fields.iter() {
-        let mut f = f.clone();
-        let mut fpath = None;
-        let dpath = f.derived_from.take();
+    let mut f = f.clone();
+    let mut fpath = None;
+    let dpath = f.derived_from.take();
+    if let Some(dpath) = dpath {
+        fpath = derive_field(&mut f, &dpath, rpath, index)?;
+    }
+    let fpath = fpath.unwrap_or_else(|| rpath.new_field(&f.name));
+    // TODO(AJM) - do we need to do anything with this range type?
+    let BitRange { offset, width, .. } = f.bit_range;
+
+    if f.is_single() && f.name.contains("%s") {
+        return Err(anyhow!("incorrect field {}", f.name));
+    }
+
+    let name = util::replace_suffix(&f.name, "");
+    let name_snake_case = name.to_snake_case_ident(span);
+    let name_constant_case = name.to_sanitized_constant_case();
+    let description_raw = f.description.as_deref().unwrap_or(""); // raw description, if absent using empty string
+    let description = util::respace(&util::escape_brackets(description_raw));
+
+    let can_read =
+        can_read && (f.access != Some(Access::WriteOnly)) && (f.access != Some(Access::WriteOnce));
+    let can_write = can_write && (f.access != Some(Access::ReadOnly));
+
+    let mask = u64::MAX >> (64 - width);
+    let hexmask = &util::digit_or_hex(mask);
+    let offset = u64::from(offset);
+    let rv = properties.reset_value.map(|rv| (rv >> offset) & mask);
+    let fty = width.to_ty()?;
+
+    // Field metadata, field arrays get one type with the index of the element as parameter
+    let spec_ty = Ident::new(&format!("{name_constant_case}_FIELD"), span);
+    let width_lit = util::unsuffixed(width as _);
+    match &f {
+        Field::Single(_) => {
+            let doc = format!("Metadata of the `{}` field", f.name);
+            let offset_lit = util::unsuffixed(offset);
+            let field_mask = util::hex(mask << offset);
+            let reset = match rv {
+                Some(rv) => {
+                    let rv = util::unsuffixed_or_bool(rv, width);
+                    quote! { Some(#rv) }
+                }
+                None => quote! { None },
+            };
+            mod_items.extend(quote! {
+                #[doc = #doc]
+                pub struct #spec_ty;
+                impl crate::FieldSpec for #spec_ty {
+                    type REG = #name_constant_case_spec;
+                    type Ux = #fty;
+                    const OFFSET: u8 = #offset_lit;
+                    const WIDTH: u8 = #width_lit;
+                    const MASK: #rty = #field_mask;
+                    const RESET: Option<#fty> = #reset;
+                }
+            });
+        }
+        Field::Array(_, de) => {
+            let doc = format!("Metadata of the elements of the `{name}` field array, `N` is the index of the element");
+            let dim = util::unsuffixed(de.dim as _);
+            let mut element_offset = quote! { N };
+            if de.dim_increment != 1 {
+                let increment = util::unsuffixed(de.dim_increment as _);
+                element_offset = quote! { N * #increment };
+            }
+            if offset != 0 {
+                let offset = util::unsuffixed(offset);
+                element_offset = quote! { #offset + #element_offset };
+            }
+            let field_mask = util::hex(mask);
+            let reset = match properties.reset_value {
+                Some(rv) => {
+                    let rv = util::hex(rv);
+                    let value = if width == 1 {
+                        quote! { (rv >> Self::OFFSET) & 1 != 0 }
+                    } else {
+                        quote! { ((rv >> Self::OFFSET) & #field_mask) as #fty }
+                    };
+                    quote! {{
+                        let rv: #rty = #rv;
+                        Some(#value)
+                    }}
+                }
+                None => quote! { None },
+            };
+            mod_items.extend(quote! {
+                #[doc = #doc]
+                pub struct #spec_ty<const N: u8>;
+                impl<const N: u8> crate::FieldSpec for #spec_ty<N> {
+                    type REG = #name_constant_case_spec;
+                    type Ux = #fty;
+                    const OFFSET: u8 = {
+                        assert!(N < #dim, "Index out of the field array");
+                        #element_offset
+                    };
+                    const WIDTH: u8 = #width_lit;
+                    const MASK: #rty = #field_mask << Self::OFFSET;
+                    const RESET: Option<#fty> = #reset;
+                }
+            });
+        }
+    }
+
+    let use_mask = if let Some(size) = properties.size {
+        size != width
+    } else {
+        true
+    };
+
+    let mut lookup_results = Vec::new();
+    for mut ev in f.enumerated_values.clone().into_iter() {
+        let mut epath = None;
+        let dpath = ev.derived_from.take();
         if let Some(dpath) = dpath {
-            fpath = derive_field(&mut f, &dpath, rpath, index)?;
+            epath = Some(derive_enumerated_values(&mut ev, &dpath, &fpath, index)?);
         }
-        let fpath = fpath.unwrap_or_else(|| rpath.new_field(&f.name));
-        // TODO(AJM) - do we need to do anything with this range type?
-        let BitRange { offset, width, .. } = f.bit_range;
+        // TODO: remove this hack
+        if let Some(epath) = epath.as_ref() {
+            ev = (*index.evs.get(epath).unwrap()).clone();
+        }
+        lookup_results.push((ev, epath));
+    }
 
-        if f.is_single() && f.name.contains("%s") {
-            return Err(anyhow!("incorrect field {}", f.name));
+    let mut evs_r = None;
+
+    let brief_suffix = if let Field::Array(_, de) = &f {
+        if let Some(range) = de.indexes_as_range() {
+            format!("[{}-{}]", *range.start(), *range.end())
+        } else {
+            let suffixes: Vec<_> = de.indexes().collect();
+            format!("[{}]", suffixes.join(","))
         }
+    } else {
+        String::new()
+    };
 
-        let name = util::replace_suffix(&f.name, "");
-        let name_snake_case = name.to_snake_case_ident(span);
-        let name_constant_case = name.to_sanitized_constant_case();
-        let description_raw = f.description.as_deref().unwrap_or(""); // raw description, if absent using empty string
-        let description = util::respace(&util::escape_brackets(description_raw));
-
-        let can_read = can_read
-            && (f.access != Some(Access::WriteOnly))
-            && (f.access != Some(Access::WriteOnce));
-        let can_write = can_write && (f.access != Some(Access::ReadOnly));
-
-        let mask = u64::MAX >> (64 - width);
-        let hexmask = &util::digit_or_hex(mask);
-        let offset = u64::from(offset);
-        let rv = properties.reset_value.map(|rv| (rv >> offset) & mask);
-        let fty = width.to_ty()?;
-
-        let use_mask = if let Some(size) = properties.size {
-            size != width
+    // If this field can be read, generate read proxy structure and value structure.
+    if can_read {
+        let cast = if width == 1 {
+            quote! { != 0 }
         } else {
-            true
+            quote! { as #fty }
         };
-
-        let mut lookup_results = Vec::new();
-        for mut ev in f.enumerated_values.clone().into_iter() {
-            let mut epath = None;
-            let dpath = ev.derived_from.take();
-            if let Some(dpath) = dpath {
-                epath = Some(derive_enumerated_values(&mut ev, &dpath, &fpath, index)?);
+        let value = if offset != 0 {
+            let offset = &util::unsuffixed(offset);
+            quote! {
+                ((self.bits >> #offset) & #hexmask) #cast
             }
-            // TODO: remove this hack
-            if let Some(epath) = epath.as_ref() {
-                ev = (*index.evs.get(epath).unwrap()).clone();
+        } else if use_mask {
+            quote! {
+                (self.bits & #hexmask) #cast
             }
-            lookup_results.push((ev, epath));
-        }
+        } else {
+            quote! {
+                self.bits
+            }
+        };
 
-        let mut evs_r = None;
+        // get a brief description for this field
+        // the suffix string from field name is removed in brief description.
+        let field_reader_brief = format!("Field `{name}{brief_suffix}` reader - {description}");
 
-        let brief_suffix = if let Field::Array(_, de) = &f {
-            if let Some(range) = de.indexes_as_range() {
-                format!("[{}-{}]", *range.start(), *range.end())
+        // get the type of value structure. It can be generated from either name field
+        // in enumeratedValues if it's an enumeration, or from field name directly if it's not.
+        let value_read_ty = if let Some((evs, _)) = lookup_filter(&lookup_results, Usage::Read) {
+            if let Some(enum_name) = &evs.name {
+                format!("{enum_name}_A").to_constant_case_ident(span)
             } else {
-                let suffixes: Vec<_> = de.indexes().collect();
-                format!("[{}]", suffixes.join(","))
+                // derived_field_value_read_ty
+                Ident::new(&format!("{name_constant_case}_A"), span)
             }
         } else {
-            String::new()
+            // raw_field_value_read_ty
+            fty.clone()
         };
 
-        // If this field can be read, generate read proxy structure and value structure.
-        if can_read {
-            let cast = if width == 1 {
-                quote! { != 0 }
-            } else {
-                quote! { as #fty }
-            };
-            let value = if offset != 0 {
-                let offset = &util::unsuffixed(offset);
-                quote! {
-                    ((self.bits >> #offset) & #hexmask) #cast
-                }
-            } else if use_mask {
-                quote! {
-                    (self.bits & #hexmask) #cast
-                }
-            } else {
-                quote! {
-                    self.bits
-                }
-            };
-
-            // get a brief description for this field
-            // the suffix string from field name is removed in brief description.
-            let field_reader_brief = format!("Field `{name}{brief_suffix}` reader - {description}");
+        // name of read proxy type
+        let reader_ty = Ident::new(&(name_constant_case.clone() + "_R"), span);
+
+        // if it's enumeratedValues and it's derived from base, don't derive the read proxy
+        // as the base has already dealt with this;
+        // if it's enumeratedValues but not derived from base, derive the reader from
+        // information in enumeratedValues;
+        // if it's not enumeratedValues, always derive the read proxy as we do not need to re-export
+        // it again from BitReader or FieldReader.
+        let should_derive_reader = match lookup_filter(&lookup_results, Usage::Read) {
+            Some((_evs, Some(_base))) => false,
+            Some((_evs, None)) => true,
+            None => true,
+        };
 
-            // get the type of value structure. It can be generated from either name field
-            // in enumeratedValues if it's an enumeration, or from field name directly if it's not.
-            let value_read_ty = if let Some((evs, _)) = lookup_filter(&lookup_results, Usage::Read)
-            {
-                if let Some(enum_name) = &evs.name {
-                    format!("{enum_name}_A").to_constant_case_ident(span)
-                } else {
-                    // derived_field_value_read_ty
-                    Ident::new(&format!("{name_constant_case}_A"), span)
-                }
+        // derive the read proxy structure if necessary.
+        if should_derive_reader {
+            let reader = if width == 1 {
+                quote! { crate::BitReader<#value_read_ty> }
             } else {
-                // raw_field_value_read_ty
-                fty.clone()
-            };
-
-            // name of read proxy type
-            let reader_ty = Ident::new(&(name_constant_case.clone() + "_R"), span);
-
-            // if it's enumeratedValues and it's derived from base, don't derive the read proxy
-            // as the base has already dealt with this;
-            // if it's enumeratedValues but not derived from base, derive the reader from
-            // information in enumeratedValues;
-            // if it's not enumeratedValues, always derive the read proxy as we do not need to re-export
-            // it again from BitReader or FieldReader.
-            let should_derive_reader = match lookup_filter(&lookup_results, Usage::Read) {
-                Some((_evs, Some(_base))) => false,
-                Some((_evs, None)) => true,
-                None => true,
+                quote! { crate::FieldReader<#fty, #value_read_ty> }
             };
-
-            // derive the read proxy structure if necessary.
-            if should_derive_reader {
-                let reader = if width == 1 {
-                    quote! { crate::BitReader<#value_read_ty> }
-                } else {
-                    quote! { crate::FieldReader<#fty, #value_read_ty> }
+            let mut readerdoc = field_reader_brief.clone();
+            if let Some(action) = f.read_action {
+                readerdoc += match action {
+                    ReadAction::Clear => "\n\nThe field is **cleared** (set to zero) following a read operation.",
+                    ReadAction::Set => "\n\nThe field is **set** (set to ones) following a read operation.",
+                    ReadAction::Modify => "\n\nThe field is **modified** in some way after a read operation.",
+                    ReadAction::ModifyExternal => "\n\nOne or more dependent resources other than the current field are immediately affected by a read operation.",
                 };
-                let mut readerdoc = field_reader_brief.clone();
-                if let Some(action) = f.read_action {
-                    readerdoc += match action {
-                        ReadAction::Clear => "\n\nThe field is **cleared** (set to zero) following a read operation.",
-                        ReadAction::Set => "\n\nThe field is **set** (set to ones) following a read operation.",
-                        ReadAction::Modify => "\n\nThe field is **modified** in some way after a read operation.",
-                        ReadAction::ModifyExternal => "\n\nOne or more dependent resources other than the current field are immediately affected by a read operation.",
-                    };
-                }
-                mod_items.extend(quote! {
-                    #[doc = #readerdoc]
-                    pub type #reader_ty = #reader;
-                });
             }
+            mod_items.extend(quote! {
+                #[doc = #readerdoc]
+                pub type #reader_ty = #reader;
+            });
+        }
 
-            // collect information on items in enumeration to generate it later.
-            let mut enum_items = TokenStream::new();
-
-            // if this is an enumeratedValues not derived from base, generate the enum structure
-            // and implement functions for each value in enumeration.
-            if let Some((evs, None)) = lookup_filter(&lookup_results, Usage::Read) {
-                // we have enumeration for read, record this. If the enumeration for write operation
-                // later on is the same as the read enumeration, we reuse and do not generate again.
-                evs_r = Some(evs);
-
-                // do we have finite definition of this enumeration in svd? If not, the later code would
-                // return an Option when the value read from field does not match any defined values.
-                let has_reserved_variant = evs.values.len() != (1 << width);
-                // parse enum variants from enumeratedValues svd record
-                let variants = Variant::from_enumerated_values(evs, config.pascal_enum_values)?;
-
-                // if there's no variant defined in enumeratedValues, generate enumeratedValues with new-type
-                // wrapper struct, and generate From conversation only.
-                // else, generate enumeratedValues into a Rust enum with functions for each variant.
-                if variants.is_empty() {
-                    // generate struct VALUE_READ_TY_A(fty) and From<fty> for VALUE_READ_TY_A.
-                    add_with_no_variants(mod_items, &value_read_ty, &fty, &description, rv);
-                } else {
-                    // generate enum VALUE_READ_TY_A { ... each variants ... } and and From<fty> for VALUE_READ_TY_A.
-                    add_from_variants(mod_items, &variants, &value_read_ty, &fty, &description, rv);
-
-                    // prepare code for each match arm. If we have reserved variant, the match operation would
-                    // return an Option, thus we wrap the return value with Some.
-                    let mut arms = TokenStream::new();
-                    for v in variants.iter().map(|v| {
-                        let i = util::unsuffixed_or_bool(v.value, width);
-                        let pc = &v.pc;
-
-                        if has_reserved_variant {
-                            quote! { #i => Some(#value_read_ty::#pc), }
-                        } else {
-                            quote! { #i => #value_read_ty::#pc, }
-                        }
-                    }) {
-                        arms.extend(v);
-                    }
+        // collect information on items in enumeration to generate it later.
+        let mut enum_items = TokenStream::new();
+
+        // if this is an enumeratedValues not derived from base, generate the enum structure
+        // and implement functions for each value in enumeration.
+        if let Some((evs, None)) = lookup_filter(&lookup_results, Usage::Read) {
+            // we have enumeration for read, record this. If the enumeration for write operation
+            // later on is the same as the read enumeration, we reuse and do not generate again.
+            evs_r = Some(evs);
+
+            // do we have finite definition of this enumeration in svd? If not, the later code would
+            // return an Option when the value read from field does not match any defined values.
+            // parse enum variants from enumeratedValues svd record
+            let variants =
+                Variant::from_enumerated_values(evs, &fpath, dont_care, config.pascal_enum_values)?;
+            // an `isDefault` value gets a variant for all the other values, it makes the
+            // enumeration total.
+            let default = Variant::default_of(evs, config.pascal_enum_values);
+            // the values of the patterns count too
+            let listed = Variant::count(&variants) + (evs.values.len() - variants.len()) as u64;
+            let has_reserved_variant = default.is_none() && listed != (1 << width);
+
+            // if there's no variant defined in enumeratedValues, generate enumeratedValues with new-type
+            // wrapper struct, and generate From conversation only.
+            // else, generate enumeratedValues into a Rust enum with functions for each variant.
+            if variants.is_empty() {
+                // generate struct VALUE_READ_TY_A(fty) and From<fty> for VALUE_READ_TY_A.
+                add_with_no_variants(mod_items, &value_read_ty, &fty, &description, rv);
+            } else {
+                // generate enum VALUE_READ_TY_A { ... each variants ... } and and From<fty> for VALUE_READ_TY_A.
+                add_from_variants(
+                    mod_items,
+                    &variants,
+                    default.as_ref(),
+                    &value_read_ty,
+                    &fty,
+                    width,
+                    &description,
+                    rv,
+                );
+
+                // prepare code for each match arm. If we have reserved variant, the match operation would
+                // return an Option, thus we wrap the return value with Some.
+                let mut arms = TokenStream::new();
+                for v in variants.iter().map(|v| {
+                    let i = v.matcher(width);
+                    let pc = &v.pc;
 
-                    // if we have reserved variant, for all values other than defined we return None.
-                    // if svd suggests it only would return defined variants but FieldReader has
-                    // other values, it's regarded as unreachable and we enter unreachable! macro.
-                    // This situation is rare and only exists if unsafe code casts any illegal value
-                    // into a FieldReader structure.
                     if has_reserved_variant {
+                        quote! { #i => Some(#value_read_ty::#pc), }
+                    } else {
+                        quote! { #i => #value_read_ty::#pc, }
+                    }
+                }) {
+                    arms.extend(v);
+                }
+
+                // if we have reserved variant, for all values other than defined we return None.
+                // if svd suggests it only would return defined variants but FieldReader has
+                // other values, it's regarded as unreachable and we enter unreachable! macro.
+                // This situation is rare and only exists if unsafe code casts any illegal value
+                // into a FieldReader structure.
+                if has_reserved_variant {
+                    arms.extend(quote! {
+                        _ => None,
+                    });
+                } else if 1 << width.to_ty_width()? != Variant::count(&variants)
+                    || variants.iter().any(|v| v.guarded(width))
+                {
+                    if let Some(default) = &default {
+                        // values not listed belong to the `isDefault` variant
+                        let pc = &default.pc;
                         arms.extend(quote! {
-                            _ => None,
+                            _ => #value_read_ty::#pc(self.bits),
                         });
-                    } else if 1 << width.to_ty_width()? != variants.len() {
+                    } else {
                         arms.extend(quote! {
                             _ => unreachable!(),
                         });
                     }
+                }
 
-                    // prepare the `variant` function. This function would return field value in
-                    // Rust structure; if we have reserved variant we return by Option.
-                    if has_reserved_variant {
-                        enum_items.extend(quote! {
-                            #[doc = "Get enumerated values variant"]
-                            #inline
-                            pub fn variant(&self) -> Option<#value_read_ty> {
-                                match self.bits {
-                                    #arms
-                                }
-                            }
-                        });
-                    } else {
-                        enum_items.extend(quote! {
+                // prepare the `variant` function. This function would return field value in
+                // Rust structure; if we have reserved variant we return by Option.
+                if has_reserved_variant {
+                    enum_items.extend(quote! {
                         #[doc = "Get enumerated values variant"]
                         #inline
-                        pub fn variant(&self) -> #value_read_ty {
+                        pub fn variant(&self) -> Option<#value_read_ty> {
                             match self.bits {
                                 #arms
                             }
-                        }});
-                    }
-
-                    // for each variant defined, we generate an `is_variant` function.
-                    for v in &variants {
-                        let pc = &v.pc;
-                        let sc = &v.nksc;
-
-                        let is_variant = Ident::new(
-                            &if sc.to_string().starts_with('_') {
-                                format!("is{sc}")
-                            } else {
-                                format!("is_{sc}")
-                            },
-                            span,
-                        );
-
-                        let doc = format!("Checks if the value of the field is `{pc}`");
-                        enum_items.extend(quote! {
-                            #[doc = #doc]
-                            #inline
-                            pub fn #is_variant(&self) -> bool {
-                                *self == #value_read_ty::#pc
-                            }
-                        });
-                    }
+                        }
+                    });
+                } else {
+                    enum_items.extend(quote! {
+                    #[doc = "Get enumerated values variant"]
+                    #inline
+                    pub fn variant(&self) -> #value_read_ty {
+                        match self.bits {
+                            #arms
+                        }
+                    }});
                 }
-            }
 
-            // if this value is derived from a base, generate `pub use` code for each read proxy and value
-            // if necessary.
-            if let Some((evs, Some(base))) = lookup_filter(&lookup_results, Usage::Read) {
-                // preserve value; if read type equals write type, writer would not generate value type again
-                evs_r = Some(evs);
-                // generate pub use field_1 reader as field_2 reader
-                let base_field = util::replace_suffix(&base.field.name, "");
-                let base_r = (base_field + "_R").to_constant_case_ident(span);
-                if !reader_derives.contains(&reader_ty) {
-                    derive_from_base(
-                        mod_items,
-                        base,
-                        &fpath,
-                        &reader_ty,
-                        &base_r,
-                        &field_reader_brief,
-                    )?;
-                    reader_derives.insert(reader_ty.clone());
-                }
-                // only pub use enum when base.register != None. if base.register == None, it emits
-                // pub use enum from same module which is not expected
-                if base.register() != fpath.register() {
-                    // use the same enum structure name
-                    if !enum_derives.contains(&value_read_ty) {
-                        derive_from_base(
-                            mod_items,
-                            base,
-                            &fpath,
-                            &value_read_ty,
-                            &value_read_ty,
-                            &description,
-                        )?;
-                        enum_derives.insert(value_read_ty.clone());
-                    }
-                }
-            }
+                // for each variant defined, we generate an `is_variant` function.
+                for v in &variants {
+                    let pc = &v.pc;
+                    let sc = &v.nksc;
 
-            if let Field::Array(_, de) = &f {
-                let increment = de.dim_increment;
-                let doc = &util::replace_suffix(&description, &brief_suffix);
-                if let Some(range) = de.indexes_as_range() {
-                    let first = *range.start();
+                    let is_variant = Ident::new(
+                        &if sc.to_string().starts_with('_') {
+                            format!("is{sc}")
+                        } else {
+                            format!("is_{sc}")
+                        },
+                        span,
+                    );
 
-                    let offset_calc = calculate_offset(first, increment, offset, true);
-                    let value = quote! { ((self.bits >> #offset_calc) & #hexmask) #cast };
-                    r_impl_items.extend(quote! {
+                    let doc = format!("Checks if the value of the field is `{pc}`");
+                    // a don't-care pattern is read as any of its values
+                    let check = if v.guarded(width) {
+                        v.check(quote! { self.bits }, width)
+                    } else if v.dont_care != 0 {
+                        quote! { true }
+                    } else {
+                        quote! { *self == #value_read_ty::#pc }
+                    };
+                    enum_items.extend(quote! {
                         #[doc = #doc]
                         #inline
-                        pub unsafe fn #name_snake_case(&self, n: u8) -> #reader_ty {
-                            #reader_ty::new ( #value )
+                        pub fn #is_variant(&self) -> bool {
+                            #check
                         }
                     });
                 }
-                for (i, suffix) in de.indexes().enumerate() {
-                    let sub_offset = offset + (i as u64) * (increment as u64);
-                    let value = if sub_offset != 0 {
-                        let sub_offset = &util::unsuffixed(sub_offset);
-                        quote! {
-                            ((self.bits >> #sub_offset) & #hexmask) #cast
-                        }
-                    } else if use_mask {
-                        quote! {
-                            (self.bits & #hexmask) #cast
-                        }
-                    } else {
-                        quote! {
-                            self.bits
-                        }
-                    };
-                    let name_snake_case_n = util::replace_suffix(&f.name, &suffix)
-                        .to_snake_case_ident(Span::call_site());
-                    let doc = util::replace_suffix(
-                        &description_with_bits(description_raw, sub_offset, width),
-                        &suffix,
+
+                if let Some(default) = &default {
+                    let pc = &default.pc;
+                    let sc = &default.nksc;
+                    let is_variant = Ident::new(
+                        &if sc.to_string().starts_with('_') {
+                            format!("is{sc}")
+                        } else {
+                            format!("is_{sc}")
+                        },
+                        span,
                     );
-                    r_impl_items.extend(quote! {
+
+                    let doc = format!("Checks if the value of the field is `{pc}`");
+                    enum_items.extend(quote! {
                         #[doc = #doc]
                         #inline
-                        pub fn #name_snake_case_n(&self) -> #reader_ty {
-                            #reader_ty::new ( #value )
+                        pub fn #is_variant(&self) -> bool {
+                            matches!(self.variant(), #value_read_ty::#pc(_))
                         }
                     });
                 }
-            } else {
-                let doc = description_with_bits(description_raw, offset, width);
+            }
+        }
+
+        // if this value is derived from a base, generate `pub use` code for each read proxy and value
+        // if necessary.
+        if let Some((evs, Some(base))) = lookup_filter(&lookup_results, Usage::Read) {
+            // preserve value; if read type equals write type, writer would not generate value type again
+            evs_r = Some(evs);
+            // generate pub use field_1 reader as field_2 reader
+            let base_field = util::replace_suffix(&base.field.name, "");
+            let base_r = (base_field + "_R").to_constant_case_ident(span);
+            if !reader_derives.contains(&reader_ty) {
+                derive_from_base(
+                    mod_items,
+                    base,
+                    &fpath,
+                    &reader_ty,
+                    &base_r,
+                    &field_reader_brief,
+                )?;
+                reader_derives.insert(reader_ty.clone());
+            }
+            // only pub use enum when base.register != None. if base.register == None, it emits
+            // pub use enum from same module which is not expected
+            if base.register() != fpath.register() {
+                // use the same enum structure name
+                if !enum_derives.contains(&value_read_ty) {
+                    derive_from_base(
+                        mod_items,
+                        base,
+                        &fpath,
+                        &value_read_ty,
+                        &value_read_ty,
+                        &description,
+                    )?;
+                    enum_derives.insert(value_read_ty.clone());
+                }
+            }
+        }
+
+        if let Field::Array(_, de) = &f {
+            let increment = de.dim_increment;
+            let doc = &util::replace_suffix(&description, &brief_suffix);
+            if let Some(range) = de.indexes_as_range() {
+                let first = *range.start();
+
+                let offset_calc = calculate_offset(first, increment, offset, true);
+                let value = quote! { ((self.bits >> #offset_calc) & #hexmask) #cast };
                 r_impl_items.extend(quote! {
                     #[doc = #doc]
                     #inline
-                    pub fn #name_snake_case(&self) -> #reader_ty {
+                    pub unsafe fn #name_snake_case(&self, n: u8) -> #reader_ty {
                         #reader_ty::new ( #value )
                     }
                 });
             }
-
-            // generate the enumeration functions prepared before.
-            if !enum_items.is_empty() {
-                mod_items.extend(quote! {
-                    impl #reader_ty {
-                        #enum_items
+            for (i, suffix) in de.indexes().enumerate() {
+                let sub_offset = offset + (i as u64) * (increment as u64);
+                let value = if sub_offset != 0 {
+                    let sub_offset = &util::unsuffixed(sub_offset);
+                    quote! {
+                        ((self.bits >> #sub_offset) & #hexmask) #cast
                     }
-                });
-            }
-        }
-
-        // If this field can be written, generate write proxy. Generate write value if it differs from
-        // the read value, or else we reuse read value.
-        if can_write {
-            let mwv = f
-                .modified_write_values
-                .or(register.modified_write_values)
-                .unwrap_or_default();
-            // gets a brief of write proxy
-            let field_writer_brief = format!("Field `{name}{brief_suffix}` writer - {description}");
-
-            let value_write_ty =
-                if let Some((evs, _)) = lookup_filter(&lookup_results, Usage::Write) {
-                    let writer_reader_different_enum = evs_r != Some(evs);
-                    let ty_suffix = if writer_reader_different_enum {
-                        "AW"
-                    } else {
-                        "A"
-                    };
-                    if let Some(enum_name) = &evs.name {
-                        format!("{enum_name}_{ty_suffix}").to_constant_case_ident(span)
-                    } else {
-                        // derived_field_value_write_ty
-                        Ident::new(&format!("{name_constant_case}_{ty_suffix}"), span)
+                } else if use_mask {
+                    quote! {
+                        (self.bits & #hexmask) #cast
                     }
                 } else {
-                    // raw_field_value_write_ty
-                    fty.clone()
+                    quote! {
+                        self.bits
+                    }
                 };
+                let name_snake_case_n =
+                    util::replace_suffix(&f.name, &suffix).to_snake_case_ident(Span::call_site());
+                let doc = util::replace_suffix(
+                    &description_with_bits(description_raw, sub_offset, width),
+                    &suffix,
+                );
+                r_impl_items.extend(quote! {
+                    #[doc = #doc]
+                    #inline
+                    pub fn #name_snake_case_n(&self) -> #reader_ty {
+                        #reader_ty::new ( #value )
+                    }
+                });
+            }
+            // safe access to the fields by index and by iteration
+            let names = de
+                .indexes()
+                .map(|suffix| util::replace_suffix(&f.name, &suffix).to_snake_case_ident(span))
+                .collect::<Vec<_>>();
+            if let Some(range) = de.indexes_as_range() {
+                let first = util::unsuffixed(*range.start() as u64);
+                let last = util::unsuffixed(*range.end() as u64);
+                let checked = Ident::new(&format!("{name_snake_case}_checked"), span);
+                let doc = format!("{doc}\n\n`None` if there is no field `n`");
+                r_impl_items.extend(quote! {
+                    #[doc = #doc]
+                    #inline
+                    pub fn #checked(&self, n: u8) -> Option<#reader_ty> {
+                        if (#first..=#last).contains(&n) {
+                            Some(unsafe { self.#name_snake_case(n) })
+                        } else {
+                            None
+                        }
+                    }
+                });
+            }
+            let iter = Ident::new(&format!("{name_snake_case}_iter"), span);
+            let doc = format!("Iterator over the fields of the array `{name}`");
+            r_impl_items.extend(quote! {
+                #[doc = #doc]
+                #inline
+                pub fn #iter(&self) -> impl Iterator<Item = #reader_ty> {
+                    IntoIterator::into_iter([#(self.#names(),)*])
+                }
+            });
+        } else {
+            let doc = description_with_bits(description_raw, offset, width);
+            r_impl_items.extend(quote! {
+                #[doc = #doc]
+                #inline
+                pub fn #name_snake_case(&self) -> #reader_ty {
+                    #reader_ty::new ( #value )
+                }
+            });
+        }
 
-            // name of write proxy type
-            let writer_ty = Ident::new(&(name_constant_case.clone() + "_W"), span);
-
-            let mut proxy_items = TokenStream::new();
-            let mut unsafety = unsafety(f.write_constraint.as_ref(), width);
-
-            // if we writes to enumeratedValues, generate its structure if it differs from read structure.
-            if let Some((evs, None)) = lookup_filter(&lookup_results, Usage::Write) {
-                // parse variants from enumeratedValues svd record
-                let variants = Variant::from_enumerated_values(evs, config.pascal_enum_values)?;
-
-                // if the write structure is finite, it can be safely written.
-                if variants.len() == 1 << width {
-                    unsafety = false;
+        // generate the enumeration functions prepared before.
+        if !enum_items.is_empty() {
+            mod_items.extend(quote! {
+                impl #reader_ty {
+                    #enum_items
                 }
+            });
+        }
+    }
 
-                // does the read and the write value has the same name? If we have the same,
-                // we can reuse read value type other than generating a new one.
-                let writer_reader_different_enum = evs_r != Some(evs);
+    // If this field can be written, generate write proxy. Generate write value if it differs from
+    // the read value, or else we reuse read value.
+    if can_write {
+        let mwv = f
+            .modified_write_values
+            .or(register.modified_write_values)
+            .unwrap_or_default();
+        // gets a brief of write proxy
+        let field_writer_brief = format!("Field `{name}{brief_suffix}` writer - {description}");
+
+        let value_write_ty = if let Some((evs, _)) = lookup_filter(&lookup_results, Usage::Write) {
+            let writer_reader_different_enum = evs_r != Some(evs);
+            let ty_suffix = if writer_reader_different_enum {
+                "AW"
+            } else {
+                "A"
+            };
+            if let Some(enum_name) = &evs.name {
+                format!("{enum_name}_{ty_suffix}").to_constant_case_ident(span)
+            } else {
+                // derived_field_value_write_ty
+                Ident::new(&format!("{name_constant_case}_{ty_suffix}"), span)
+            }
+        } else {
+            // raw_field_value_write_ty
+            fty.clone()
+        };
 
-                // generate write value structure and From conversation if we can't reuse read value structure.
-                if writer_reader_different_enum {
-                    if variants.is_empty() {
-                        add_with_no_variants(mod_items, &value_write_ty, &fty, &description, rv);
-                    } else {
-                        add_from_variants(
-                            mod_items,
-                            &variants,
-                            &value_write_ty,
-                            &fty,
-                            &description,
-                            rv,
-                        );
-                    }
-                }
+        // name of write proxy type
+        let writer_ty = Ident::new(&(name_constant_case.clone() + "_W"), span);
 
-                // for each variant defined, generate a write function to this field.
-                for v in &variants {
-                    let pc = &v.pc;
-                    let sc = &v.sc;
-                    let doc = util::escape_brackets(&util::respace(&v.doc));
-                    proxy_items.extend(quote! {
-                        #[doc = #doc]
-                        #inline
-                        pub fn #sc(self) -> &'a mut W {
-                            self.variant(#value_write_ty::#pc)
-                        }
-                    });
-                }
+        let mut proxy_items = TokenStream::new();
+        let mut unsafety = unsafety(f.write_constraint.as_ref(), width);
+        let has_write_variants = match lookup_filter(&lookup_results, Usage::Write) {
+            Some((evs, epath)) => {
+                let fpath = epath.as_ref().map_or(&fpath, |epath| &epath.field);
+                !Variant::from_enumerated_values(evs, fpath, dont_care, config.pascal_enum_values)?
+                    .is_empty()
             }
+            None => false,
+        };
 
-            // derive writer. We derive writer if the write proxy is in current register module,
-            // or writer in different register have different _SPEC structures
-            let should_derive_writer = match lookup_filter(&lookup_results, Usage::Write) {
-                Some((_evs, Some(base))) => base.register() != fpath.register(),
-                Some((_evs, None)) => true,
-                None => true,
-            };
+        // if we writes to enumeratedValues, generate its structure if it differs from read structure.
+        if let Some((evs, None)) = lookup_filter(&lookup_results, Usage::Write) {
+            // parse variants from enumeratedValues svd record
+            let variants =
+                Variant::from_enumerated_values(evs, &fpath, dont_care, config.pascal_enum_values)?;
+            let default = Variant::default_of(evs, config.pascal_enum_values);
+
+            // if the write structure is finite, it can be safely written. So can it be if
+            // all the other values are defined with `isDefault`.
+            if Variant::count(&variants) == 1 << width
+                || (default.is_some() && !variants.is_empty())
+            {
+                unsafety = false;
+            }
 
-            // derive writer structure by type alias to generic write proxy structure.
-            if should_derive_writer {
-                let proxy = if width == 1 {
-                    let wproxy = Ident::new(
-                        match mwv {
-                            ModifiedWriteValues::Modify => "BitWriter",
-                            ModifiedWriteValues::OneToSet | ModifiedWriteValues::Set => {
-                                "BitWriter1S"
-                            }
-                            ModifiedWriteValues::ZeroToClear | ModifiedWriteValues::Clear => {
-                                "BitWriter0C"
-                            }
-                            ModifiedWriteValues::OneToClear => "BitWriter1C",
-                            ModifiedWriteValues::ZeroToSet => "BitWriter0C",
-                            ModifiedWriteValues::OneToToggle => "BitWriter1T",
-                            ModifiedWriteValues::ZeroToToggle => "BitWriter0T",
-                        },
-                        span,
-                    );
-                    quote! { crate::#wproxy<'a, #rty, #name_constant_case_spec, #value_write_ty, O> }
+            // does the read and the write value has the same name? If we have the same,
+            // we can reuse read value type other than generating a new one.
+            let writer_reader_different_enum = evs_r != Some(evs);
+
+            // generate write value structure and From conversation if we can't reuse read value structure.
+            if writer_reader_different_enum {
+                if variants.is_empty() {
+                    add_with_no_variants(mod_items, &value_write_ty, &fty, &description, rv);
                 } else {
-                    let wproxy = Ident::new(
-                        if unsafety {
-                            "FieldWriter"
-                        } else {
-                            "FieldWriterSafe"
-                        },
-                        span,
+                    add_from_variants(
+                        mod_items,
+                        &variants,
+                        default.as_ref(),
+                        &value_write_ty,
+                        &fty,
+                        width,
+                        &description,
+                        rv,
                     );
-                    let width = &util::unsuffixed(width as _);
-                    quote! { crate::#wproxy<'a, #rty, #name_constant_case_spec, #fty, #value_write_ty, #width, O> }
-                };
-                mod_items.extend(quote! {
-                    #[doc = #field_writer_brief]
-                    pub type #writer_ty<'a, const O: u8> = #proxy;
-                });
+                }
             }
 
-            // generate proxy items from collected information
-            if !proxy_items.is_empty() {
-                mod_items.extend(quote! {
-                    impl<'a, const O: u8> #writer_ty<'a, O> {
-                        #proxy_items
+            // for each variant defined, generate a write function to this field.
+            for v in &variants {
+                let pc = &v.pc;
+                let sc = &v.sc;
+                let doc = util::escape_brackets(&util::respace(&v.doc));
+                proxy_items.extend(quote! {
+                    #[doc = #doc]
+                    #inline
+                    pub fn #sc(self) -> &'a mut W {
+                        self.variant(#value_write_ty::#pc)
                     }
                 });
             }
+        }
 
-            if let Some((evs, Some(base))) = lookup_filter(&lookup_results, Usage::Write) {
-                // if base.register == None, it emits pub use structure from same module.
-                if base.register() != fpath.register() {
-                    let writer_reader_different_enum = evs_r != Some(evs);
-                    if writer_reader_different_enum {
-                        // use the same enum structure name
-                        if !writer_enum_derives.contains(&value_write_ty) {
-                            derive_from_base(
-                                mod_items,
-                                base,
-                                &fpath,
-                                &value_write_ty,
-                                &value_write_ty,
-                                &description,
-                            )?;
-                            writer_enum_derives.insert(value_write_ty.clone());
-                        }
+        // derive writer. We derive writer if the write proxy is in current register module,
+        // or writer in different register have different _SPEC structures
+        let should_derive_writer = match lookup_filter(&lookup_results, Usage::Write) {
+            Some((_evs, Some(base))) => base.register() != fpath.register(),
+            Some((_evs, None)) => true,
+            None => true,
+        };
+
+        // the writer proxy, how the `<field>_checked` writer of a field array writes `variant`
+        // with it, and when that returns `None`
+        let no_field = "`None` if there is no field `n`".to_string();
+        let variant_write: fn(&Ident) -> TokenStream =
+            |w| quote! { Some(self.#w().variant(variant)) };
+        let (proxy, write_variant, none_doc) = if width == 1 {
+            let wproxy = Ident::new(
+                match mwv {
+                    ModifiedWriteValues::Modify => "BitWriter",
+                    ModifiedWriteValues::OneToSet | ModifiedWriteValues::Set => "BitWriter1S",
+                    ModifiedWriteValues::ZeroToClear | ModifiedWriteValues::Clear => "BitWriter0C",
+                    ModifiedWriteValues::OneToClear => "BitWriter1C",
+                    ModifiedWriteValues::ZeroToSet => "BitWriter0C",
+                    ModifiedWriteValues::OneToToggle => "BitWriter1T",
+                    ModifiedWriteValues::ZeroToToggle => "BitWriter0T",
+                },
+                span,
+            );
+            (
+                quote! { crate::#wproxy<'a, #rty, #name_constant_case_spec, #value_write_ty, O> },
+                variant_write,
+                no_field,
+            )
+        } else if unsafety
+            && f.write_constraint == Some(WriteConstraint::UseEnumeratedValues(true))
+            && has_write_variants
+        {
+            // only the enumerated values may be written, there is no `bits`
+            let width = &util::unsuffixed(width as _);
+            (
+                quote! { crate::FieldWriterEnum<'a, #rty, #name_constant_case_spec, #fty, #value_write_ty, #width, O> },
+                variant_write,
+                no_field,
+            )
+        } else if let (true, Some(WriteConstraint::Range(range))) = (unsafety, f.write_constraint) {
+            // values in the range can be written safely with `set`. So can the enumerated
+            // values, a raw value must not bypass the range check through `variant`
+            if should_derive_writer && has_write_variants {
+                proxy_items.extend(quote! {
+                    #[doc = "Writes `variant` to the field"]
+                    #inline
+                    pub fn variant(self, variant: #value_write_ty) -> &'a mut W {
+                        unsafe { self.bits(variant.into()) }
                     }
+                });
+            }
+            let width = &util::unsuffixed(width as _);
+            let min = &util::unsuffixed(range.min);
+            let max = &util::unsuffixed(range.max);
+            let set_write: fn(&Ident) -> TokenStream =
+                |w| quote! { self.#w().set(variant.into()).ok() };
+            (
+                quote! { crate::FieldWriterChecked<'a, #rty, #name_constant_case_spec, #fty, #value_write_ty, #width, O, #min, #max> },
+                set_write,
+                format!(
+                    "{no_field} or if `variant` is not in `{}..={}`",
+                    range.min, range.max
+                ),
+            )
+        } else {
+            let wproxy = Ident::new(
+                if unsafety {
+                    "FieldWriter"
                 } else {
-                    // if base.register == None, derive write from the same module. This is allowed because both
-                    // the generated and source write proxy are in the same module.
-                    // we never reuse writer for writer in different module does not have the same _SPEC strcuture,
-                    // thus we cannot write to current register using re-exported write proxy.
-
-                    // generate pub use field_1 writer as field_2 writer
-                    let base_field = util::replace_suffix(&base.field.name, "");
-                    let base_w = (base_field + "_W").to_constant_case_ident(span);
-                    if !writer_derives.contains(&writer_ty) {
+                    "FieldWriterSafe"
+                },
+                span,
+            );
+            let width = &util::unsuffixed(width as _);
+            (
+                quote! { crate::#wproxy<'a, #rty, #name_constant_case_spec, #fty, #value_write_ty, #width, O> },
+                variant_write,
+                no_field,
+            )
+        };
+
+        // derive writer structure by type alias to generic write proxy structure.
+        if should_derive_writer {
+            mod_items.extend(quote! {
+                #[doc = #field_writer_brief]
+                pub type #writer_ty<'a, const O: u8> = #proxy;
+            });
+        }
+
+        // generate proxy items from collected information
+        if !proxy_items.is_empty() {
+            mod_items.extend(quote! {
+                impl<'a, const O: u8> #writer_ty<'a, O> {
+                    #proxy_items
+                }
+            });
+        }
+
+        if let Some((evs, Some(base))) = lookup_filter(&lookup_results, Usage::Write) {
+            // if base.register == None, it emits pub use structure from same module.
+            if base.register() != fpath.register() {
+                let writer_reader_different_enum = evs_r != Some(evs);
+                if writer_reader_different_enum {
+                    // use the same enum structure name
+                    if !writer_enum_derives.contains(&value_write_ty) {
                         derive_from_base(
                             mod_items,
                             base,
                             &fpath,
-                            &writer_ty,
-                            &base_w,
-                            &field_writer_brief,
+                            &value_write_ty,
+                            &value_write_ty,
+                            &description,
                         )?;
-                        writer_derives.insert(writer_ty.clone());
+                        writer_enum_derives.insert(value_write_ty.clone());
                     }
                 }
+            } else {
+                // if base.register == None, derive write from the same module. This is allowed because both
+                // the generated and source write proxy are in the same module.
+                // we never reuse writer for writer in different module does not have the same _SPEC strcuture,
+                // thus we cannot write to current register using re-exported write proxy.
+
+                // generate pub use field_1 writer as field_2 writer
+                let base_field = util::replace_suffix(&base.field.name, "");
+                let base_w = (base_field + "_W").to_constant_case_ident(span);
+                if !writer_derives.contains(&writer_ty) {
+                    derive_from_base(
+                        mod_items,
+                        base,
+                        &fpath,
+                        &writer_ty,
+                        &base_w,
+                        &field_writer_brief,
+                    )?;
+                    writer_derives.insert(writer_ty.clone());
+                }
             }
+        }
+
+        if let Field::Array(_, de) = &f {
+            let increment = de.dim_increment;
+            let doc = &util::replace_suffix(&description, &brief_suffix);
+            w_impl_items.extend(quote! {
+                #[doc = #doc]
+                #inline
+                #[must_use]
+                pub unsafe fn #name_snake_case<const O: u8>(&mut self) -> #writer_ty<O> {
+                    #writer_ty::new(self)
+                }
+            });
+
+            for (i, suffix) in de.indexes().enumerate() {
+                let sub_offset = offset + (i as u64) * (increment as u64);
+                let name_snake_case_n =
+                    &util::replace_suffix(&f.name, &suffix).to_snake_case_ident(Span::call_site());
+                let doc = util::replace_suffix(
+                    &description_with_bits(description_raw, sub_offset, width),
+                    &suffix,
+                );
+                let sub_offset = util::unsuffixed(sub_offset);
 
-            if let Field::Array(_, de) = &f {
-                let increment = de.dim_increment;
-                let doc = &util::replace_suffix(&description, &brief_suffix);
                 w_impl_items.extend(quote! {
                     #[doc = #doc]
                     #inline
                     #[must_use]
-                    pub unsafe fn #name_snake_case<const O: u8>(&mut self) -> #writer_ty<O> {
+                    pub fn #name_snake_case_n(&mut self) -> #writer_ty<#sub_offset> {
                         #writer_ty::new(self)
                     }
                 });
-
-                for (i, suffix) in de.indexes().enumerate() {
-                    let sub_offset = offset + (i as u64) * (increment as u64);
-                    let name_snake_case_n = &util::replace_suffix(&f.name, &suffix)
-                        .to_snake_case_ident(Span::call_site());
-                    let doc = util::replace_suffix(
-                        &description_with_bits(description_raw, sub_offset, width),
-                        &suffix,
-                    );
-                    let sub_offset = util::unsuffixed(sub_offset as u64);
-
-                    w_impl_items.extend(quote! {
-                        #[doc = #doc]
-                        #inline
-                        #[must_use]
-                        pub fn #name_snake_case_n(&mut self) -> #writer_ty<#sub_offset> {
-                            #writer_ty::new(self)
-                        }
-                    });
-                }
-            } else {
-                let doc = description_with_bits(description_raw, offset, width);
-                let offset = util::unsuffixed(offset as u64);
+            }
+            if let Some(range) = de.indexes_as_range() {
+                let arms = range.zip(de.indexes()).map(|(n, suffix)| {
+                    let n = util::unsuffixed(n as u64);
+                    let name_snake_case_n =
+                        util::replace_suffix(&f.name, &suffix).to_snake_case_ident(span);
+                    let write = write_variant(&name_snake_case_n);
+                    quote! { #n => #write, }
+                });
+                let checked = Ident::new(&format!("{name_snake_case}_checked"), span);
+                let doc = format!(
+                    "Writes `variant` to the field `n` of the array `{name}`\n\n{none_doc}"
+                );
                 w_impl_items.extend(quote! {
                     #[doc = #doc]
                     #inline
-                    #[must_use]
-                    pub fn #name_snake_case(&mut self) -> #writer_ty<#offset> {
-                        #writer_ty::new(self)
+                    pub fn #checked(&mut self, n: u8, variant: #value_write_ty) -> Option<&mut Self> {
+                        match n {
+                            #(#arms)*
+                            _ => None,
+                        }
                     }
                 });
             }
+        } else {
+            let doc = description_with_bits(description_raw, offset, width);
+            let offset = util::unsuffixed(offset);
+            w_impl_items.extend(quote! {
+                #[doc = #doc]
+                #inline
+                #[must_use]
+                pub fn #name_snake_case(&mut self) -> #writer_ty<#offset> {
+                    #writer_ty::new(self)
+                }
+            });
         }
     }
-
     Ok(())
 }
 
@@ -995,41 +1297,140 @@ struct Variant {
     pc: Ident,
     nksc: Ident,
     sc: Ident,
+    /// Value written for the variant
     value: u64,
+    /// Bits read as anything for the variant, from a don't-care pattern like `#1xx`
+    dont_care: u64,
 }
 
 impl Variant {
-    fn from_enumerated_values(evs: &EnumeratedValues, pc: bool) -> Result<Vec<Self>> {
-        let span = Span::call_site();
+    fn from_enumerated_values(
+        evs: &EnumeratedValues,
+        fpath: &FieldPath,
+        dont_care: &Patterns,
+        pc: bool,
+    ) -> Result<Vec<Self>> {
         evs.values
             .iter()
             // filter out all reserved variants, as we should not
             // generate code for them
-            .filter(|field| field.name.to_lowercase() != "reserved" && field.is_default == None)
+            .filter(|field| {
+                field.name.to_lowercase() != "reserved" && field.is_default != Some(true)
+            })
             .map(|ev| {
                 let value = ev
                     .value
                     .ok_or_else(|| anyhow!("EnumeratedValue {} has no `<value>` field", ev.name))?;
-
-                let nksc = ev.name.to_sanitized_not_keyword_snake_case();
-                let sc = util::sanitize_keyword(nksc.clone());
-                Ok(Variant {
-                    doc: ev
-                        .description
-                        .clone()
-                        .unwrap_or_else(|| format!("`{value:b}`")),
-                    pc: if pc {
-                        ev.name.to_pascal_case_ident(span)
-                    } else {
-                        ev.name.to_constant_case_ident(span)
-                    },
-                    nksc: Ident::new(&nksc, span),
-                    sc: Ident::new(&sc, span),
-                    value,
-                })
+                // the value of a pattern like `#1xx` has the don't-care bits cleared, it is the
+                // one written
+                let mut variant = Variant::new(ev, String::new(), value, pc);
+                variant.dont_care = dont_care.get(fpath, ev).map_or(0, |p| p.dont_care);
+                variant.doc = ev
+                    .description
+                    .clone()
+                    .unwrap_or_else(|| format!("`{}`", variant.pattern()));
+                Ok(variant)
             })
             .collect::<Result<Vec<_>>>()
     }
+
+    /// Binary pattern of the values, with `x` for the don't-care bits
+    fn pattern(&self) -> String {
+        let width = 64 - (self.value | self.dont_care).leading_zeros();
+        (0..width.max(1))
+            .rev()
+            .map(|bit| {
+                if self.dont_care >> bit & 1 == 1 {
+                    'x'
+                } else if self.value >> bit & 1 == 1 {
+                    '1'
+                } else {
+                    '0'
+                }
+            })
+            .collect()
+    }
+
+    /// The value, or the pattern of the values, for docs
+    fn label(&self) -> String {
+        if self.dont_care != 0 {
+            format!("#{}", self.pattern())
+        } else {
+            self.value.to_string()
+        }
+    }
+
+    /// Number of values read as one of `variants`
+    fn count(variants: &[Self]) -> u64 {
+        variants
+            .iter()
+            .map(|v| {
+                1u64.checked_shl(v.dont_care.count_ones())
+                    .unwrap_or(u64::MAX)
+            })
+            .fold(0, u64::saturating_add)
+    }
+
+    /// Whether the values read as the variant are matched with a guard, which makes a `match`
+    /// need a catch-all arm
+    fn guarded(&self, width: u32) -> bool {
+        self.dont_care != 0 && width != 1
+    }
+
+    /// Whether `bits` is read as the variant, comparing the bits that are not don't-care as there
+    /// may be too many values to list
+    fn check(&self, bits: TokenStream, width: u32) -> TokenStream {
+        let field_mask = u64::MAX >> (64 - width);
+        let mask = util::unsuffixed(field_mask & !self.dont_care);
+        let value = util::unsuffixed(self.value);
+        quote! { #bits & #mask == #value }
+    }
+
+    /// Arm pattern matching the values read as the variant
+    fn matcher(&self, width: u32) -> TokenStream {
+        if self.guarded(width) {
+            let check = self.check(quote! { bits }, width);
+            quote! { bits if #check }
+        } else if self.dont_care != 0 {
+            // a one bit field is read as the variant whatever its bit
+            quote! { false | true }
+        } else {
+            let value = util::unsuffixed_or_bool(self.value, width);
+            quote! { #value }
+        }
+    }
+
+    /// The `isDefault` value standing for all the values not listed, its variant carries the bits
+    fn default_of(evs: &EnumeratedValues, pc: bool) -> Option<Self> {
+        evs.values
+            .iter()
+            .find(|ev| ev.is_default == Some(true))
+            .map(|ev| {
+                let doc = ev
+                    .description
+                    .clone()
+                    .unwrap_or_else(|| "Any other value".to_string());
+                Variant::new(ev, doc, 0, pc)
+            })
+    }
+
+    fn new(ev: &EnumeratedValue, doc: String, value: u64, pc: bool) -> Self {
+        let span = Span::call_site();
+        let nksc = ev.name.to_sanitized_not_keyword_snake_case();
+        let sc = util::sanitize_keyword(nksc.clone());
+        Variant {
+            doc,
+            pc: if pc {
+                ev.name.to_pascal_case_ident(span)
+            } else {
+                ev.name.to_constant_case_ident(span)
+            },
+            nksc: Ident::new(&nksc, span),
+            sc: Ident::new(&sc, span),
+            value,
+            dont_care: 0,
+        }
+    }
 }
 
 fn add_with_no_variants(
@@ -1064,15 +1465,43 @@ fn add_with_no_variants(
     });
 }
 
+#[allow(clippy::too_many_arguments)]
 fn add_from_variants(
     mod_items: &mut TokenStream,
     variants: &[Variant],
+    default: Option<&Variant>,
     pc: &Ident,
     fty: &Ident,
+    width: u32,
     desc: &str,
     reset_value: Option<u64>,
 ) {
-    let (repr, cast) = if fty == "bool" {
+    let (repr, cast) = if let Some(default) = default {
+        // The catch-all variant has a field, values are matched instead of cast
+        let value_width = if fty == "bool" { 1 } else { 0 };
+        let arms = variants.iter().map(|v| {
+            let pcv = &v.pc;
+            let value = util::unsuffixed_or_bool(v.value, value_width);
+            quote! { #pc::#pcv => #value, }
+        });
+        let default = &default.pc;
+        // Bits past the field width are dropped, not written over the neighbouring fields
+        let bits = if fty == "bool" || [8, 16, 32, 64].contains(&width) {
+            quote! { bits }
+        } else {
+            let mask = util::unsuffixed((1 << width) - 1);
+            quote! { bits & #mask }
+        };
+        (
+            quote! {},
+            quote! {
+                match variant {
+                    #(#arms)*
+                    #pc::#default(bits) => #bits,
+                }
+            },
+        )
+    } else if fty == "bool" {
         (quote! {}, quote! { variant as u8 != 0 })
     } else {
         (quote! { #[repr(#fty)] }, quote! { variant as _ })
@@ -1080,16 +1509,36 @@ fn add_from_variants(
 
     let mut vars = TokenStream::new();
     for v in variants.iter().map(|v| {
-        let desc = util::escape_brackets(&util::respace(&format!("{}: {}", v.value, v.doc)));
+        let desc = util::escape_brackets(&util::respace(&format!("{}: {}", v.label(), v.doc)));
         let pcv = &v.pc;
         let pcval = &util::unsuffixed(v.value);
-        quote! {
-            #[doc = #desc]
-            #pcv = #pcval,
+        if default.is_some() {
+            quote! {
+                #[doc = #desc]
+                #pcv,
+            }
+        } else {
+            quote! {
+                #[doc = #desc]
+                #pcv = #pcval,
+            }
         }
     }) {
         vars.extend(v);
     }
+    if let Some(default) = default {
+        let desc = format!(
+            "{}\n\nOnly read for the values not listed, which compare unequal to the listed \
+             variants even when the bits match",
+            util::respace(&default.doc)
+        );
+        let desc = util::escape_brackets(&desc);
+        let pcv = &default.pc;
+        vars.extend(quote! {
+            #[doc = #desc]
+            #pcv(#fty),
+        });
+    }
 
     let desc = if let Some(rv) = reset_value {
         format!("{desc}\n\nValue on reset: {rv}")
@@ -1195,3 +1644,113 @@ fn lookup_filter(
         .find(|evsbase| evsbase.0.usage == Some(usage))
         .or_else(|| evs.first())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{generate, Config};
+
+    fn device(fields: &str) -> String {
+        format!(
+            r#"<device>
+  <name>DEV</name>
+  <addressUnitBits>8</addressUnitBits>
+  <width>32</width>
+  <size>32</size>
+  <access>read-write</access>
+  <resetValue>0</resetValue>
+  <resetMask>0xFFFFFFFF</resetMask>
+  <peripherals>
+    <peripheral>
+      <name>PER</name>
+      <baseAddress>0x40000000</baseAddress>
+      <addressBlock><offset>0</offset><size>0x10</size><usage>registers</usage></addressBlock>
+      <registers>
+        <register>
+          <name>CTRL</name>
+          <addressOffset>0</addressOffset>
+          <fields>{fields}</fields>
+        </register>
+      </registers>
+    </peripheral>
+  </peripherals>
+</device>"#
+        )
+    }
+
+    fn render(fields: &str) -> String {
+        let lib_rs = generate(&device(fields), &Config::default())
+            .unwrap()
+            .lib_rs;
+        lib_rs.split_whitespace().collect()
+    }
+
+    #[test]
+    fn checked_field_array_writer() {
+        // a field array with a write range, with and without enumerated values
+        let lib_rs = render(
+            "<field>
+              <dim>2</dim>
+              <dimIncrement>2</dimIncrement>
+              <name>EN%s</name>
+              <bitOffset>0</bitOffset>
+              <bitWidth>2</bitWidth>
+              <writeConstraint><range><minimum>1</minimum><maximum>2</maximum></range></writeConstraint>
+              <enumeratedValues>
+                <enumeratedValue><name>SLOW</name><value>1</value></enumeratedValue>
+                <enumeratedValue><name>FAST</name><value>2</value></enumeratedValue>
+              </enumeratedValues>
+            </field>
+            <field>
+              <dim>2</dim>
+              <dimIncrement>2</dimIncrement>
+              <name>LVL%s</name>
+              <bitOffset>8</bitOffset>
+              <bitWidth>2</bitWidth>
+              <writeConstraint><range><minimum>1</minimum><maximum>2</maximum></range></writeConstraint>
+            </field>",
+        );
+        for field in ["en", "lvl"] {
+            for n in 0..2 {
+                assert!(lib_rs.contains(&format!("self.{field}{n}().set(variant.into()).ok()")));
+                assert!(!lib_rs.contains(&format!("self.{field}{n}().variant(variant)")));
+            }
+        }
+    }
+
+    #[test]
+    fn dont_care_values() {
+        let lib_rs = render(
+            "<field>
+              <name>MODE</name>
+              <bitOffset>0</bitOffset>
+              <bitWidth>12</bitWidth>
+              <enumeratedValues>
+                <enumeratedValue><name>OFF</name><value>0</value></enumeratedValue>
+                <enumeratedValue><name>HIGH</name><value>#1xxxxxxxxxxx</value></enumeratedValue>
+                <enumeratedValue><name>ODD</name><value>#0xxxxxxxxxx1</value></enumeratedValue>
+              </enumeratedValues>
+            </field>",
+        );
+        assert!(lib_rs.contains("0=>Some(MODE_A::OFF),"));
+        assert!(lib_rs.contains("bitsifbits&2048==2048=>Some(MODE_A::HIGH),"));
+        assert!(lib_rs.contains("bitsifbits&2049==1=>Some(MODE_A::ODD),"));
+        assert!(lib_rs.contains("pubfnis_odd(&self)->bool{self.bits&2049==1}"));
+        // the pattern is written with the don't-care bits cleared
+        assert!(lib_rs.contains("HIGH=2048,"));
+        assert!(lib_rs.contains("ODD=1,"));
+    }
+
+    #[test]
+    fn field_array_writer() {
+        let lib_rs = render(
+            "<field>
+              <dim>2</dim>
+              <dimIncrement>2</dimIncrement>
+              <name>EN%s</name>
+              <bitOffset>0</bitOffset>
+              <bitWidth>2</bitWidth>
+            </field>",
+        );
+        assert!(lib_rs.contains("0=>Some(self.en0().variant(variant)),"));
+    }
+}
@@ -0,0 +1,164 @@
+//! Warnings about questionable but rendered items
+//!
+//! Rendering functions push a [`Warning`] into the [`Warnings`] passed down to them with a path
+//! relative to the item they render, callers [`nest`](Warnings::nest) them under the item name.
+//! [`Config::deny_warnings`] and [`Config::deny`] turn them into errors.
+
+use crate::source::{Location, SourceMap};
+use crate::util::Config;
+use anyhow::{anyhow, Result};
+use std::fmt;
+
+/// Kind of a [`Warning`], its code never changes once released
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Code {
+    /// Overlapping registers got a union type name conflicting with another one and were renamed
+    TypeNameConflict,
+    /// Register has no description
+    MissingDescription,
+    /// The `const fn` computing the address of a register has the name of another method and is
+    /// left out
+    AddressFnConflict,
+}
+
+impl Code {
+    pub const ALL: [Code; 3] = [
+        Self::TypeNameConflict,
+        Self::MissingDescription,
+        Self::AddressFnConflict,
+    ];
+
+    /// Stable identifier of the code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TypeNameConflict => "W001",
+            Self::MissingDescription => "W002",
+            Self::AddressFnConflict => "W003",
+        }
+    }
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Code {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .iter()
+            .find(|c| c.as_str() == s)
+            .copied()
+            .ok_or_else(|| anyhow!("Unknown warning code {}", s))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Code {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Something rendered that is likely not what the SVD author meant
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning {
+    pub code: Code,
+    /// Dot separated path of the item, like `TIM1.CR1`
+    pub path: String,
+    pub message: String,
+    /// Where the item is in the input, see [`Warnings::locate`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub location: Option<Location>,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "warning[{}] {}: {}", self.code, self.path, self.message)?;
+        if let Some(location) = &self.location {
+            write!(f, "\n{}", location.snippet())?;
+        }
+        Ok(())
+    }
+}
+
+/// Collected warnings, in rendering order
+#[derive(Clone, Debug, Default)]
+pub struct Warnings(pub Vec<Warning>);
+
+impl Warnings {
+    /// Adds a warning about the item being rendered
+    pub fn push(&mut self, code: Code, message: String) {
+        self.0.push(Warning {
+            code,
+            path: String::new(),
+            message,
+            location: None,
+        });
+    }
+
+    /// Adds the warnings of the item `name`
+    pub fn nest(&mut self, name: &str, inner: Warnings) {
+        self.0.extend(inner.0.into_iter().map(|w| Warning {
+            path: if w.path.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}.{}", name, w.path)
+            },
+            ..w
+        }));
+    }
+
+    /// Finds the items in the input the device was loaded from
+    pub fn locate(&mut self, sources: &SourceMap) {
+        for w in &mut self.0 {
+            w.location = sources.locate(&w.path);
+        }
+    }
+
+    /// Fails with a [`DeniedWarnings`] if any warning is denied by the configuration, call
+    /// [`locate`](Self::locate) first for the warnings to carry their locations
+    pub fn deny(&self, config: &Config) -> Result<()> {
+        let codes = config
+            .deny
+            .iter()
+            .flat_map(|d| d.split(','))
+            .map(|c| c.trim().parse())
+            .collect::<Result<Vec<Code>>>()?;
+        let denied = self
+            .0
+            .iter()
+            .filter(|w| config.deny_warnings || codes.contains(&w.code))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !denied.is_empty() {
+            return Err(DeniedWarnings {
+                denied,
+                warnings: self.0.clone(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Error of [`Warnings::deny`]
+#[derive(Debug)]
+pub struct DeniedWarnings {
+    /// The warnings denied by the configuration
+    pub denied: Vec<Warning>,
+    /// All the warnings, denied or not
+    pub warnings: Vec<Warning>,
+}
+
+impl fmt::Display for DeniedWarnings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} denied warning(s)", self.denied.len())
+    }
+}
+
+impl std::error::Error for DeniedWarnings {}
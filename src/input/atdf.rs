@@ -0,0 +1,613 @@
+//! Conversion of Microchip (Atmel) ATDF device files
+//!
+//! An ATDF file describes a device in two parts: `modules` holds the register groups, bitfields
+//! and value groups of every peripheral type, and the `device` element lists the module
+//! instances with their base addresses together with the interrupt table. Each register group
+//! referenced by an instance becomes a peripheral. Instances that reuse the register group of
+//! an earlier instance are emitted as `derivedFrom` that instance.
+
+use std::collections::HashMap;
+
+use super::xml::{attr, child, children, mask_runs, parse_int};
+use crate::svd::{
+    Access, AddressBlock, AddressBlockUsage, BitRange, ClusterInfo, Cpu, Device, DimElement,
+    Endian, EnumeratedValue, EnumeratedValues, Field, FieldInfo, Interrupt, PeripheralInfo,
+    RegisterCluster, RegisterInfo, RegisterProperties, ValidateLevel,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use log::warn;
+use roxmltree::{Document, Node};
+
+/// Parses an ATDF file into a [`Device`]
+pub fn parse(input: &str, lvl: ValidateLevel) -> Result<Device> {
+    let doc = Document::parse(input).context("Error parsing ATDF XML")?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "avr-tools-device-file" {
+        bail!(
+            "Expected `avr-tools-device-file` root element, found `{}`",
+            root.tag_name().name()
+        );
+    }
+    let device = child(root, "devices")
+        .and_then(|d| child(d, "device"))
+        .ok_or_else(|| anyhow!("ATDF file has no `device` element"))?;
+    let modules =
+        child(root, "modules").ok_or_else(|| anyhow!("ATDF file has no `modules` element"))?;
+
+    let name = attr(device, "name")?;
+    let properties = properties(device);
+    let cpu = cpu(
+        device.attribute("architecture").unwrap_or(""),
+        &properties,
+        lvl,
+    )?;
+    let width = match device.attribute("architecture") {
+        Some(arch) if arch.starts_with("AVR") => 8,
+        _ => 32,
+    };
+
+    let mut peripherals = Vec::new();
+    let mut instances = HashMap::new();
+    let mut defined = HashMap::new();
+    for module in child(device, "peripherals")
+        .into_iter()
+        .flat_map(|p| children(p, "module"))
+    {
+        let module_name = attr(module, "name")?;
+        let definition = children(modules, "module")
+            .find(|m| m.attribute("name") == Some(module_name))
+            .ok_or_else(|| anyhow!("Module `{}` is not defined", module_name))?;
+        let ctx = Ctx {
+            module: definition,
+            default_size: width / 8,
+            lvl,
+        };
+        for instance in children(module, "instance") {
+            let instance_name = attr(instance, "name")?;
+            let groups = children(instance, "register-group").collect::<Vec<_>>();
+            for group in &groups {
+                let name = if groups.len() == 1 {
+                    instance_name
+                } else {
+                    attr(*group, "name")?
+                };
+                let peripheral = ctx
+                    .peripheral(instance, *group, name, &mut defined)
+                    .with_context(|| format!("Error converting peripheral `{}`", name))?;
+                instances.entry(instance_name).or_insert(peripherals.len());
+                peripherals.push(peripheral);
+            }
+        }
+    }
+
+    // Interrupts belong to the device in ATDF. Attach each one to the instance it names, if
+    // any, or to the instance named like its first word otherwise. SVD has no device-level
+    // interrupts, so the others are left out.
+    for interrupt in child(device, "interrupts")
+        .into_iter()
+        .flat_map(|i| children(i, "interrupt"))
+    {
+        let name = attr(interrupt, "name")?;
+        if name == "RESET" {
+            continue;
+        }
+        let owner = interrupt
+            .attribute("module-instance")
+            .or_else(|| name.split('_').next())
+            .and_then(|instance| instances.get(instance).copied());
+        let interrupt = Interrupt::builder()
+            .name(name.to_string())
+            .description(caption(interrupt))
+            .value(parse_int(attr(interrupt, "index")?)? as u32)
+            .build(lvl)?;
+        match owner {
+            Some(i) => peripherals[i].interrupt.push(interrupt),
+            None => warn!(
+                "Interrupt {} matches no peripheral instance and is left out",
+                interrupt.name
+            ),
+        }
+    }
+
+    let description = match device.attribute("family") {
+        Some(family) => format!("{} {}", family, name),
+        None => name.to_string(),
+    };
+    Ok(Device::builder()
+        .name(name.to_string())
+        .series(device.attribute("family").map(String::from))
+        .version("1.0".to_string())
+        .description(description)
+        .cpu(cpu)
+        .address_unit_bits(8)
+        .width(width)
+        .default_register_properties(
+            RegisterProperties::new()
+                .size(Some(width))
+                .access(Some(Access::ReadWrite))
+                .reset_value(Some(0))
+                .reset_mask(Some(u64::MAX >> (64 - width))),
+        )
+        .peripherals(peripherals.into_iter().map(|p| p.single()).collect())
+        .build(lvl)?)
+}
+
+struct Ctx<'a, 'i> {
+    /// Definition of the module being converted
+    module: Node<'a, 'i>,
+    /// Register size in bytes when not given
+    default_size: u32,
+    lvl: ValidateLevel,
+}
+
+impl<'a, 'i> Ctx<'a, 'i> {
+    fn peripheral(
+        &self,
+        instance: Node,
+        group_ref: Node<'a, 'i>,
+        name: &str,
+        defined: &mut HashMap<(&'a str, &'a str), (String, u64)>,
+    ) -> Result<PeripheralInfo> {
+        let module_name = attr(self.module, "name")?;
+        let group_name = attr(group_ref, "name-in-module")?;
+        let offset = parse_int(attr(group_ref, "offset")?)?;
+        let builder = PeripheralInfo::builder()
+            .name(name.to_string())
+            .description(caption(instance).or_else(|| caption(self.module)))
+            .group_name(Some(module_name.to_string()));
+
+        if let Some((base, shift)) = defined.get(&(module_name, group_name)) {
+            return Ok(builder
+                .base_address(offset + shift)
+                .derived_from(Some(base.clone()))
+                .build(self.lvl)?);
+        }
+
+        let group = self.register_group(group_name)?;
+        let mut registers = self.group_items(group)?;
+        // Registers of megaAVR modules use absolute data space addresses with a zero instance
+        // offset, move those to the base address
+        let shift = registers
+            .iter()
+            .map(|(rc, _)| offset_of(rc))
+            .min()
+            .unwrap_or(0);
+        for (rc, _) in &mut registers {
+            match rc {
+                RegisterCluster::Register(r) => r.address_offset -= shift,
+                RegisterCluster::Cluster(c) => c.address_offset -= shift,
+            }
+        }
+        let size = registers
+            .iter()
+            .map(|(_, end)| *end - shift)
+            .max()
+            .unwrap_or(0);
+        defined.insert((module_name, group_name), (name.to_string(), shift.into()));
+
+        let builder = if size > 0 {
+            builder.address_block(Some(vec![AddressBlock::builder()
+                .offset(0)
+                .size(size)
+                .usage(AddressBlockUsage::Registers)
+                .build(self.lvl)?]))
+        } else {
+            builder
+        };
+        Ok(builder
+            .base_address(offset + u64::from(shift))
+            .registers(Some(registers.into_iter().map(|(rc, _)| rc).collect()))
+            .build(self.lvl)?)
+    }
+
+    fn register_group(&self, name: &str) -> Result<Node<'a, 'i>> {
+        children(self.module, "register-group")
+            .find(|g| g.attribute("name") == Some(name))
+            .ok_or_else(|| anyhow!("Register group `{}` is not defined", name))
+    }
+
+    /// Registers and nested groups of `group` with the offset right past their end
+    fn group_items(&self, group: Node) -> Result<Vec<(RegisterCluster, u32)>> {
+        let mut items = Vec::new();
+        for node in group.children().filter(|n| n.is_element()) {
+            match node.tag_name().name() {
+                "register" => items.push(self.register(node).with_context(|| {
+                    format!(
+                        "Error converting register `{}`",
+                        attr(node, "name").unwrap_or("")
+                    )
+                })?),
+                "register-group" => {
+                    if let Some(cluster) = self.cluster(node).with_context(|| {
+                        format!(
+                            "Error converting register group `{}`",
+                            attr(node, "name").unwrap_or("")
+                        )
+                    })? {
+                        items.push(cluster);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(items)
+    }
+
+    fn register(&self, node: Node) -> Result<(RegisterCluster, u32)> {
+        let name = attr(node, "name")?;
+        let offset = parse_int(attr(node, "offset")?)? as u32;
+        let size = match node.attribute("size") {
+            Some(size) => parse_int(size)? as u32,
+            None => self.default_size,
+        };
+        if !(1..=8).contains(&size) {
+            bail!("Register size of {} bytes is not supported", size);
+        }
+        let count = node.attribute("count").map(parse_int).transpose()?;
+        let fields = self.bitfields(node, size * 8)?;
+
+        let info = RegisterInfo::builder()
+            .name(match count {
+                Some(count) if count > 1 => format!("{}[%s]", name),
+                _ => name.to_string(),
+            })
+            .description(caption(node))
+            .address_offset(offset)
+            .size(Some(size * 8))
+            .access(access(node))
+            .reset_value(node.attribute("initval").map(parse_int).transpose()?)
+            .fields((!fields.is_empty()).then(|| fields))
+            .build(self.lvl)?;
+        let (dim, count) = self.dim(count, size)?;
+        Ok((
+            RegisterCluster::Register(info.maybe_array(dim)),
+            offset + size * count,
+        ))
+    }
+
+    /// Converts a reference to another register group of the module into a cluster
+    fn cluster(&self, node: Node) -> Result<Option<(RegisterCluster, u32)>> {
+        let target = match node.attribute("name-in-module") {
+            Some(target) => self.register_group(target)?,
+            None => return Ok(None),
+        };
+        let items = self.group_items(target)?;
+        if items.is_empty() {
+            return Ok(None);
+        }
+        let offset = parse_int(attr(node, "offset")?)? as u32;
+        let span = match node.attribute("size") {
+            Some(size) => parse_int(size)? as u32,
+            None => items.iter().map(|(_, end)| *end).max().unwrap_or(0),
+        };
+        let count = node.attribute("count").map(parse_int).transpose()?;
+        let name = attr(node, "name")?;
+
+        let info = ClusterInfo::builder()
+            .name(match count {
+                Some(count) if count > 1 => format!("{}[%s]", name),
+                _ => name.to_string(),
+            })
+            .description(caption(node).or_else(|| caption(target)))
+            .address_offset(offset)
+            .children(items.into_iter().map(|(rc, _)| rc).collect())
+            .build(self.lvl)?;
+        let (dim, count) = self.dim(count, span)?;
+        Ok(Some((
+            RegisterCluster::Cluster(info.maybe_array(dim)),
+            offset + span * count,
+        )))
+    }
+
+    fn dim(&self, count: Option<u64>, increment: u32) -> Result<(Option<DimElement>, u32)> {
+        Ok(match count {
+            Some(count) if count > 1 => (
+                Some(
+                    DimElement::builder()
+                        .dim(count as u32)
+                        .dim_increment(increment)
+                        .build(self.lvl)?,
+                ),
+                count as u32,
+            ),
+            _ => (None, 1),
+        })
+    }
+
+    fn bitfields(&self, register: Node, size: u32) -> Result<Vec<Field>> {
+        let mut fields = Vec::new();
+        for bitfield in children(register, "bitfield") {
+            let name = attr(bitfield, "name")?;
+            let mask = parse_int(attr(bitfield, "mask")?)?;
+            let runs = mask_runs(mask & (u64::MAX >> (64 - size)));
+            if runs.is_empty() {
+                warn!("Bitfield {} has an empty mask, skipping", name);
+                continue;
+            }
+            let values = match bitfield.attribute("values") {
+                Some(values) if runs.len() == 1 => self.value_group(values, runs[0].1)?,
+                Some(values) => {
+                    warn!(
+                        "Bitfield {} is not contiguous, dropping its value group {}",
+                        name, values
+                    );
+                    Vec::new()
+                }
+                None => Vec::new(),
+            };
+            // A bitfield spread over several runs of bits becomes one field per run
+            for (i, (offset, width)) in runs.iter().enumerate() {
+                let (name, description) = if runs.len() == 1 {
+                    (name.to_string(), caption(bitfield))
+                } else {
+                    (
+                        format!("{}_{}", name, i),
+                        caption(bitfield).map(|c| format!("{} (part {})", c, i)),
+                    )
+                };
+                fields.push(
+                    FieldInfo::builder()
+                        .name(name)
+                        .description(description)
+                        .bit_range(BitRange::from_offset_width(*offset, *width))
+                        .access(access(bitfield))
+                        .enumerated_values(values.clone())
+                        .build(self.lvl)?
+                        .single(),
+                );
+            }
+        }
+        Ok(fields)
+    }
+
+    fn value_group(&self, name: &str, width: u32) -> Result<Vec<EnumeratedValues>> {
+        let group = match children(self.module, "value-group")
+            .find(|g| g.attribute("name") == Some(name))
+        {
+            Some(group) => group,
+            None => {
+                warn!("Value group {} is not defined", name);
+                return Ok(Vec::new());
+            }
+        };
+        let mut values = Vec::new();
+        for value in children(group, "value") {
+            let value_name = attr(value, "name")?;
+            let v = parse_int(attr(value, "value")?)?;
+            if width < 64 && v >> width != 0 {
+                warn!(
+                    "Value {} of group {} doesn't fit in {} bits, skipping",
+                    value_name, name, width
+                );
+                continue;
+            }
+            values.push(
+                EnumeratedValue::builder()
+                    .name(value_name.to_string())
+                    .description(caption(value))
+                    .value(Some(v))
+                    .build(self.lvl)?,
+            );
+        }
+        Ok(if values.is_empty() {
+            Vec::new()
+        } else {
+            vec![EnumeratedValues::builder().values(values).build(self.lvl)?]
+        })
+    }
+}
+
+fn offset_of(rc: &RegisterCluster) -> u32 {
+    match rc {
+        RegisterCluster::Register(r) => r.address_offset,
+        RegisterCluster::Cluster(c) => c.address_offset,
+    }
+}
+
+fn caption(node: Node) -> Option<String> {
+    node.attribute("caption")
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .map(String::from)
+}
+
+fn access(node: Node) -> Option<Access> {
+    match node.attribute("rw") {
+        Some("R") => Some(Access::ReadOnly),
+        Some("W") => Some(Access::WriteOnly),
+        Some("RW") => Some(Access::ReadWrite),
+        _ => None,
+    }
+}
+
+/// Values of all `property` elements of the device
+fn properties<'a>(device: Node<'a, '_>) -> HashMap<&'a str, &'a str> {
+    child(device, "property-groups")
+        .into_iter()
+        .flat_map(|groups| children(groups, "property-group"))
+        .flat_map(|group| children(group, "property"))
+        .filter_map(|p| Some((p.attribute("name")?, p.attribute("value")?)))
+        .collect()
+}
+
+/// Cortex-M core of SAM devices, AVR devices have none
+fn cpu(
+    architecture: &str,
+    properties: &HashMap<&str, &str>,
+    lvl: ValidateLevel,
+) -> Result<Option<Cpu>> {
+    let core = match architecture.strip_prefix("CORTEX-") {
+        Some(core) => core,
+        None => return Ok(None),
+    };
+    let name = format!("C{}", core);
+    let property = |name: &str| properties.get(name).map(|v| parse_int(v)).transpose();
+    let flag = |name: &str| property(name).map(|v| v.map_or(false, |v| v != 0));
+    let revision = property(&format!("__{}_REV", name))?.unwrap_or(0);
+    let nvic_priority_bits = match property("__NVIC_PRIO_BITS")? {
+        Some(bits) => bits as u32,
+        None if core.starts_with("M0") || core.starts_with("M23") => 2,
+        None => 4,
+    };
+    Ok(Some(
+        Cpu::builder()
+            .name(name)
+            .revision(format!("r{}p{}", revision >> 8, revision & 0xff))
+            .endian(Endian::Little)
+            .mpu_present(flag("__MPU_PRESENT")?)
+            .fpu_present(flag("__FPU_PRESENT")?)
+            .nvic_priority_bits(nvic_priority_bits)
+            .has_vendor_systick(flag("__Vendor_SysTickConfig")?)
+            .build(lvl)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svd::{Cluster, Register};
+
+    fn atdf(registers: &str) -> String {
+        format!(
+            r#"<avr-tools-device-file>
+  <devices>
+    <device name="ATtest" architecture="AVR8" family="megaAVR">
+      <peripherals>
+        <module name="TC">
+          <instance name="TC0" caption="Timer 0">
+            <register-group name="TC0" name-in-module="TC" offset="0x40"/>
+          </instance>
+          <instance name="TC1">
+            <register-group name="TC1" name-in-module="TC" offset="0x80"/>
+          </instance>
+        </module>
+      </peripherals>
+      <interrupts>
+        <interrupt index="0" name="RESET"/>
+        <interrupt index="1" name="TC0_OVF" caption="Overflow"/>
+        <interrupt index="2" name="CAPT" module-instance="TC1"/>
+        <interrupt index="3" name="ADC"/>
+      </interrupts>
+    </device>
+  </devices>
+  <modules>
+    <module name="TC" caption="Timer">
+      <register-group name="TC" caption="Timer">
+{registers}
+      </register-group>
+      <register-group name="CH">
+        <register name="CC" offset="0x0" size="2"/>
+      </register-group>
+      <value-group name="MODE">
+        <value name="OFF" value="0"/>
+        <value name="ON" value="1"/>
+        <value name="BIG" value="4"/>
+      </value-group>
+    </module>
+  </modules>
+</avr-tools-device-file>"#
+        )
+    }
+
+    const CTRL: &str = r#"<register name="CTRL" offset="0x0" size="1" initval="0x10" rw="RW">
+          <bitfield name="MODE" mask="0x03" values="MODE"/>
+          <bitfield name="SPLIT" mask="0x48" rw="R"/>
+        </register>"#;
+
+    fn fields(register: &Register) -> Vec<(&str, u32, u32)> {
+        register
+            .fields()
+            .map(|f| (f.name.as_str(), f.bit_offset(), f.bit_width()))
+            .collect()
+    }
+
+    #[test]
+    fn registers() {
+        let device = parse(&atdf(CTRL), ValidateLevel::Disabled).unwrap();
+        assert_eq!(device.width, 8);
+        let tc0 = &device.peripherals[0];
+        assert_eq!(tc0.base_address, 0x40);
+        assert_eq!(tc0.description.as_deref(), Some("Timer 0"));
+        let ctrl = tc0.get_register("CTRL").unwrap();
+        assert_eq!(ctrl.properties.size, Some(8));
+        assert_eq!(ctrl.properties.reset_value, Some(0x10));
+        assert_eq!(ctrl.properties.access, Some(Access::ReadWrite));
+        // The second instance reuses the register group of the first one
+        assert_eq!(device.peripherals[1].base_address, 0x80);
+        assert_eq!(device.peripherals[1].derived_from.as_deref(), Some("TC0"));
+    }
+
+    #[test]
+    fn bitfields() {
+        let device = parse(&atdf(CTRL), ValidateLevel::Disabled).unwrap();
+        let ctrl = device.peripherals[0].get_register("CTRL").unwrap();
+        assert_eq!(
+            fields(ctrl),
+            [("MODE", 0, 2), ("SPLIT_0", 3, 1), ("SPLIT_1", 6, 1)]
+        );
+        let split = ctrl.get_field("SPLIT_1").unwrap();
+        assert_eq!(split.access, Some(Access::ReadOnly));
+        // Values that don't fit in the bitfield are left out
+        let mode = ctrl.get_field("MODE").unwrap();
+        let values = mode.enumerated_values[0]
+            .values
+            .iter()
+            .map(|v| (v.name.as_str(), v.value))
+            .collect::<Vec<_>>();
+        assert_eq!(values, [("OFF", Some(0)), ("ON", Some(1))]);
+    }
+
+    #[test]
+    fn register_groups() {
+        let registers = r#"<register name="DATA" offset="0x0" size="2" count="3"/>
+        <register-group name="CH" name-in-module="CH" offset="0x8" size="4" count="2"/>"#;
+        let device = parse(&atdf(registers), ValidateLevel::Disabled).unwrap();
+        let tc0 = &device.peripherals[0];
+        match tc0.get_register("DATA[%s]").unwrap() {
+            Register::Array(info, dim) => {
+                assert_eq!(info.properties.size, Some(16));
+                assert_eq!((dim.dim, dim.dim_increment), (3, 2));
+            }
+            Register::Single(_) => panic!("DATA is not an array"),
+        }
+        match tc0.get_cluster("CH[%s]").unwrap() {
+            Cluster::Array(info, dim) => {
+                assert_eq!(info.address_offset, 8);
+                assert_eq!((dim.dim, dim.dim_increment), (2, 4));
+                assert!(info.get_register("CC").is_some());
+            }
+            Cluster::Single(_) => panic!("CH is not an array"),
+        }
+        let block = &tc0.address_block.as_ref().unwrap()[0];
+        assert_eq!(block.size, 0x10);
+    }
+
+    #[test]
+    fn interrupts() {
+        let device = parse(&atdf(CTRL), ValidateLevel::Disabled).unwrap();
+        let interrupts = |i: usize| {
+            device.peripherals[i]
+                .interrupt
+                .iter()
+                .map(|i| (i.name.as_str(), i.value))
+                .collect::<Vec<_>>()
+        };
+        // By the name prefix, and by `module-instance`. RESET and ADC have no instance.
+        assert_eq!(interrupts(0), [("TC0_OVF", 1)]);
+        assert_eq!(interrupts(1), [("CAPT", 2)]);
+    }
+
+    #[test]
+    fn register_sizes() {
+        for size in ["0", "16"] {
+            let register = format!(r#"<register name="R" offset="0x0" size="{size}"/>"#);
+            assert!(parse(&atdf(&register), ValidateLevel::Disabled).is_err());
+        }
+        let wide = r#"<register name="R" offset="0x0" size="8">
+          <bitfield name="TOP" mask="0x8000000000000000"/>
+        </register>"#;
+        let device = parse(&atdf(wide), ValidateLevel::Disabled).unwrap();
+        let r = device.peripherals[0].get_register("R").unwrap();
+        assert_eq!(fields(r), [("TOP", 63, 1)]);
+    }
+}
@@ -0,0 +1,371 @@
+//! Enumerated values with don't-care bits
+//!
+//! SVD allows values like `#1xx` where `x` bits may be anything. `svd-parser` reads them as if the
+//! `x` were `0` and the device keeps only that value, so [`Patterns`] reads the don't-care bits
+//! from the input file itself. The generator then matches the values read against the pattern.
+//! JSON and YAML inputs may give such a `value` as a `"#1xx"` string, which is replaced by its
+//! value with the don't-care bits cleared before parsing, like `svd-parser` does for XML.
+
+use crate::svd::EnumeratedValue;
+use crate::util::SourceType;
+use std::collections::HashMap;
+use svd_parser::expand::FieldPath;
+
+/// A value with don't-care bits
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    /// The value with the don't-care bits cleared, as `svd-parser` reads it
+    pub value: u64,
+    /// The don't-care bits
+    pub dont_care: u64,
+}
+
+impl Pattern {
+    /// Parses a binary `#` value with at least one don't-care bit
+    pub fn parse(text: &str) -> Option<Self> {
+        let bits = text.trim().strip_prefix('#')?;
+        if bits.is_empty() || bits.len() > 64 || !bits.chars().all(|c| "01xX".contains(c)) {
+            return None;
+        }
+        let mut pattern = Self {
+            value: 0,
+            dont_care: 0,
+        };
+        for (i, c) in bits.chars().rev().enumerate() {
+            match c {
+                '1' => pattern.value |= 1 << i,
+                'x' | 'X' => pattern.dont_care |= 1 << i,
+                _ => {}
+            }
+        }
+        if pattern.dont_care == 0 {
+            None
+        } else {
+            Some(pattern)
+        }
+    }
+}
+
+/// The don't-care patterns of the enumerated values of an input file, by the path of their field
+/// and their name
+#[derive(Debug, Default)]
+pub struct Patterns(HashMap<(String, String), Pattern>);
+
+impl Patterns {
+    /// Reads the patterns of `text`, in the given format. Only SVD files have them, in XML, YAML or
+    /// JSON. A file that can't be read has none, loading it reports the error.
+    pub fn new(text: &str, format: SourceType) -> Self {
+        let mut patterns = Self::default();
+        match format {
+            SourceType::Xml => {
+                if let Ok(doc) = roxmltree::Document::parse(text) {
+                    let root = doc.root_element();
+                    if root.has_tag_name("device") {
+                        patterns.xml(root, &mut Vec::new());
+                    }
+                }
+            }
+            #[cfg(feature = "yaml")]
+            SourceType::Yaml => {
+                if let Ok(mut yaml) = serde_yaml::from_str(text) {
+                    patterns = take_yaml(&mut yaml);
+                }
+            }
+            #[cfg(feature = "json")]
+            SourceType::Json => {
+                if let Ok(mut json) = serde_json::from_str(text) {
+                    patterns = take_json(&mut json);
+                }
+            }
+            #[allow(unreachable_patterns)]
+            _ => {}
+        }
+        patterns
+    }
+
+    /// The pattern of the enumerated value `ev` of the field at `field`, if it has one
+    pub fn get(&self, field: &FieldPath, ev: &EnumeratedValue) -> Option<Pattern> {
+        self.0
+            .get(&(field.to_string(), ev.name.clone()))
+            .copied()
+            .filter(|pattern| ev.value == Some(pattern.value))
+    }
+
+    fn insert(&mut self, path: &[String], name: &str, pattern: Pattern) {
+        self.0.insert((path.join("."), name.to_string()), pattern);
+    }
+
+    /// Collects the patterns of the descendants of `node`, `path` holds the names of the
+    /// peripheral, clusters, register and field it is in
+    fn xml(&mut self, node: roxmltree::Node, path: &mut Vec<String>) {
+        for child in node.children().filter(|c| c.is_element()) {
+            let text = |tag| {
+                child
+                    .children()
+                    .find(|c| c.has_tag_name(tag))
+                    .and_then(|c| c.text())
+                    .map(str::trim)
+            };
+            match child.tag_name().name() {
+                "peripheral" | "cluster" | "register" | "field" => {
+                    path.push(text("name").unwrap_or_default().to_string());
+                    self.xml(child, path);
+                    path.pop();
+                }
+                "enumeratedValue" => {
+                    if let (Some(name), Some(pattern)) =
+                        (text("name"), text("value").and_then(Pattern::parse))
+                    {
+                        self.insert(path, name, pattern);
+                    }
+                }
+                _ => self.xml(child, path),
+            }
+        }
+    }
+}
+
+/// Replaces the don't-care patterns of the enumerated values of a JSON SVD file by their value
+/// with the don't-care bits cleared, and returns them
+#[cfg(feature = "json")]
+pub fn take_json(json: &mut serde_json::Value) -> Patterns {
+    use serde_json::Value;
+
+    fn take(json: &mut Value, path: &mut Vec<String>, patterns: &mut Patterns) {
+        match json {
+            Value::Array(items) => items.iter_mut().for_each(|i| take(i, path, patterns)),
+            // Enumerated values
+            Value::Object(map) if map.contains_key("values") => {
+                for ev in map.values_mut().filter_map(Value::as_array_mut).flatten() {
+                    let name = ev.get("name").and_then(Value::as_str).map(str::to_string);
+                    let pattern = ev
+                        .get("value")
+                        .and_then(Value::as_str)
+                        .and_then(Pattern::parse);
+                    if let (Some(name), Some(pattern)) = (name, pattern) {
+                        ev["value"] = pattern.value.into();
+                        patterns.insert(path, &name, pattern);
+                    }
+                }
+            }
+            Value::Object(map) => {
+                let name = map.get("name").and_then(Value::as_str).map(str::to_string);
+                let named = name.is_some();
+                path.extend(name);
+                map.values_mut().for_each(|v| take(v, path, patterns));
+                if named {
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut patterns = Patterns::default();
+    // The device is not part of the paths
+    if let Value::Object(device) = json {
+        for v in device.values_mut() {
+            take(v, &mut Vec::new(), &mut patterns);
+        }
+    }
+    patterns
+}
+
+/// Replaces the don't-care patterns of the enumerated values of a YAML SVD file by their value
+/// with the don't-care bits cleared, and returns them
+#[cfg(feature = "yaml")]
+pub fn take_yaml(yaml: &mut serde_yaml::Value) -> Patterns {
+    use serde_yaml::Value;
+
+    fn take(yaml: &mut Value, path: &mut Vec<String>, patterns: &mut Patterns) {
+        match yaml {
+            Value::Sequence(items) => items.iter_mut().for_each(|i| take(i, path, patterns)),
+            // Enumerated values
+            Value::Mapping(map) if map.contains_key("values") => {
+                for ev in map
+                    .values_mut()
+                    .filter_map(Value::as_sequence_mut)
+                    .flatten()
+                {
+                    let name = ev.get("name").and_then(Value::as_str).map(str::to_string);
+                    let pattern = ev
+                        .get("value")
+                        .and_then(Value::as_str)
+                        .and_then(Pattern::parse);
+                    if let (Some(name), Some(pattern)) = (name, pattern) {
+                        ev["value"] = pattern.value.into();
+                        patterns.insert(path, &name, pattern);
+                    }
+                }
+            }
+            Value::Mapping(map) => {
+                let name = map.get("name").and_then(Value::as_str).map(str::to_string);
+                let named = name.is_some();
+                path.extend(name);
+                map.values_mut().for_each(|v| take(v, path, patterns));
+                if named {
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut patterns = Patterns::default();
+    // The device is not part of the paths
+    if let Value::Mapping(device) = yaml {
+        for v in device.values_mut() {
+            take(v, &mut Vec::new(), &mut patterns);
+        }
+    }
+    patterns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svd::EnumeratedValue;
+    use svd_parser::expand::BlockPath;
+
+    fn ev(name: &str, value: u64) -> EnumeratedValue {
+        EnumeratedValue::builder()
+            .name(name.to_string())
+            .value(Some(value))
+            .build(svd_parser::ValidateLevel::Disabled)
+            .unwrap()
+    }
+
+    fn mode() -> FieldPath {
+        BlockPath::new("PER")
+            .new_cluster("CL")
+            .new_register("CTRL")
+            .new_field("MODE")
+    }
+
+    #[test]
+    fn patterns() {
+        let pattern = |value, dont_care| Some(Pattern { value, dont_care });
+        assert_eq!(Pattern::parse("#1xx"), pattern(0b100, 0b11));
+        assert_eq!(Pattern::parse(" #x0X1 "), pattern(0b1, 0b1010));
+        assert_eq!(Pattern::parse("#1xxxxxxxxxxx"), pattern(1 << 11, 0x7ff));
+        assert_eq!(Pattern::parse("#101"), None);
+        assert_eq!(Pattern::parse("5"), None);
+        assert_eq!(Pattern::parse("#1yx"), None);
+    }
+
+    #[test]
+    fn xml() {
+        let xml = r#"<device>
+  <name>DEV</name>
+  <peripherals>
+    <peripheral>
+      <name>PER</name>
+      <registers>
+        <cluster>
+          <name>CL</name>
+          <register>
+            <name>CTRL</name>
+            <fields>
+              <field>
+                <name>MODE</name>
+                <enumeratedValues>
+                  <!-- <enumeratedValue><name>OLD</name><value>#xx</value></enumeratedValue> -->
+                  <enumeratedValue id="a">
+                    <name> HIGH </name>
+                    <value><![CDATA[#1xx]]></value>
+                  </enumeratedValue>
+                  <enumeratedValue><name>OFF</name><value>#000</value></enumeratedValue>
+                </enumeratedValues>
+              </field>
+            </fields>
+          </register>
+        </cluster>
+      </registers>
+    </peripheral>
+  </peripherals>
+</device>"#;
+        let patterns = Patterns::new(xml, SourceType::Xml);
+        assert_eq!(patterns.0.len(), 1);
+        assert_eq!(
+            patterns.get(&mode(), &ev("HIGH", 4)),
+            Some(Pattern {
+                value: 4,
+                dont_care: 3
+            })
+        );
+        // Another value, or the same one elsewhere
+        assert_eq!(patterns.get(&mode(), &ev("HIGH", 5)), None);
+        let other = BlockPath::new("PER").new_register("CTRL").new_field("MODE");
+        assert_eq!(patterns.get(&other, &ev("HIGH", 4)), None);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json() {
+        let mut json = serde_json::json!({
+            "name": "DEV",
+            "peripherals": [{
+                "name": "PER",
+                "registers": [{ "cluster": { "name": "CL", "children": [{ "register": {
+                    "name": "CTRL",
+                    "fields": [{
+                        "name": "MODE",
+                        "enumeratedValues": [{ "values": [
+                            { "name": "HIGH", "value": "#1x" },
+                            { "name": "LOW", "value": 0 },
+                        ]}]
+                    }]
+                }}]}}]
+            }]
+        });
+        let patterns = take_json(&mut json);
+        let values = &json["peripherals"][0]["registers"][0]["cluster"]["children"][0]["register"]
+            ["fields"][0]["enumeratedValues"][0]["values"];
+        assert_eq!(
+            values,
+            &serde_json::json!([
+                { "name": "HIGH", "value": 2 },
+                { "name": "LOW", "value": 0 },
+            ])
+        );
+        assert_eq!(
+            patterns.get(&mode(), &ev("HIGH", 2)),
+            Some(Pattern {
+                value: 2,
+                dont_care: 1
+            })
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml() {
+        let text = "name: DEV
+peripherals:
+- name: PER
+  registers:
+  - cluster:
+      name: CL
+      children:
+      - register:
+          name: CTRL
+          fields:
+          - name: MODE
+            enumeratedValues:
+            - values:
+              - name: HIGH
+                value: '#x1'
+";
+        let mut yaml: serde_yaml::Value = serde_yaml::from_str(text).unwrap();
+        let patterns = take_yaml(&mut yaml);
+        assert_eq!(
+            patterns.get(&mode(), &ev("HIGH", 1)),
+            Some(Pattern {
+                value: 1,
+                dont_care: 2
+            })
+        );
+        assert!(!serde_yaml::to_string(&yaml).unwrap().contains('#'));
+        assert_eq!(Patterns::new(text, SourceType::Yaml).0, patterns.0);
+    }
+}
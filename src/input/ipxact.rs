@@ -0,0 +1,471 @@
+//! Conversion of IP-XACT (IEEE 1685) component descriptions
+//!
+//! Both the 2009 (`spirit`) and 2014 (`ipxact`) schemas are accepted, elements are matched by
+//! their local name. Every address block of the component memory maps becomes a peripheral,
+//! register files become clusters. Values have to be literals: decimal, `0x` prefixed or
+//! Verilog style (`'h1F`, `8'b0101`), expressions referring to parameters are rejected.
+
+use super::xml::{child, children, parse_int, text, text_req};
+use crate::svd::{
+    Access, AddressBlock, AddressBlockUsage, BitRange, ClusterInfo, Device, DimElement,
+    EnumeratedValue, EnumeratedValues, Field, FieldInfo, ModifiedWriteValues, PeripheralInfo,
+    ReadAction, RegisterCluster, RegisterInfo, RegisterProperties, Usage, ValidateLevel,
+    WriteConstraint, WriteConstraintRange,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use log::warn;
+use roxmltree::{Document, Node};
+
+/// Returns `true` if the root element of the XML `input` is an IP-XACT `component`
+pub fn is_component(input: &str) -> bool {
+    let mut rest = input;
+    // Skip the declaration, processing instructions and comments in front of the root element
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        let end = if rest.starts_with("<?") {
+            rest.find("?>")
+        } else if rest.starts_with("<!--") {
+            rest.find("-->")
+        } else if rest.starts_with("<!") {
+            rest.find('>')
+        } else {
+            let tag = rest[1..]
+                .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                .next()
+                .unwrap_or("");
+            return tag.rsplit(':').next() == Some("component");
+        };
+        match end {
+            Some(end) => rest = &rest[end + 1..],
+            None => return false,
+        }
+    }
+    false
+}
+
+/// Parses an IP-XACT component into a [`Device`]
+pub fn parse(input: &str, lvl: ValidateLevel) -> Result<Device> {
+    let doc = Document::parse(input).context("Error parsing IP-XACT XML")?;
+    let component = doc.root_element();
+    if component.tag_name().name() != "component" {
+        bail!(
+            "Expected IP-XACT `component` root element, found `{}`",
+            component.tag_name().name()
+        );
+    }
+
+    let maps = child(component, "memoryMaps")
+        .into_iter()
+        .flat_map(|m| children(m, "memoryMap"))
+        .collect::<Vec<_>>();
+    let mut peripherals = Vec::new();
+    let mut width = None;
+    for map in &maps {
+        let map_name = text_req(*map, "name")?;
+        let unit = text(*map, "addressUnitBits")
+            .map(parse_value)
+            .transpose()?
+            .unwrap_or(8) as u32;
+        if unit == 0 || unit % 8 != 0 {
+            bail!(
+                "addressUnitBits {} of memory map {} is not a multiple of 8",
+                unit,
+                map_name
+            );
+        }
+        let ctx = Ctx { unit, lvl };
+        if child(*map, "bank").is_some() || child(*map, "subspaceMap").is_some() {
+            warn!(
+                "Banks and subspace maps of memory map {} are skipped",
+                map_name
+            );
+        }
+        for block in children(*map, "addressBlock") {
+            let block_name = text_req(block, "name")?;
+            // Address blocks of different memory maps may share a name
+            let name = if maps.len() > 1 {
+                format!("{}_{}", map_name, block_name)
+            } else {
+                block_name.to_string()
+            };
+            let peripheral = ctx
+                .peripheral(block, name)
+                .with_context(|| format!("Error converting address block `{}`", block_name))?;
+            if width.is_none() {
+                width = text(block, "width").map(parse_value).transpose()?;
+            }
+            peripherals.push(peripheral.single());
+        }
+    }
+
+    let width = width.unwrap_or(32) as u32;
+    Ok(Device::builder()
+        .vendor(text(component, "vendor").map(String::from))
+        .name(text_req(component, "name")?.to_string())
+        .version(text(component, "version").unwrap_or("1.0").to_string())
+        .description(description(component).unwrap_or_default())
+        .address_unit_bits(8)
+        .width(width)
+        .default_register_properties(
+            RegisterProperties::new()
+                .size(Some(width))
+                .access(Some(Access::ReadWrite))
+                .reset_value(Some(0))
+                .reset_mask(Some(u64::MAX >> (64 - width))),
+        )
+        .peripherals(peripherals)
+        .build(lvl)?)
+}
+
+struct Ctx {
+    /// Bits per address of the memory map, a multiple of 8
+    unit: u32,
+    lvl: ValidateLevel,
+}
+
+impl Ctx {
+    /// Number of bytes in `units` addresses of the memory map
+    fn bytes(&self, units: u64) -> u64 {
+        units * u64::from(self.unit / 8)
+    }
+
+    fn peripheral(&self, block: Node, name: String) -> Result<PeripheralInfo> {
+        let range = self.bytes(parse_value(text_req(block, "range")?)?);
+        let usage = match text(block, "usage") {
+            Some("memory") => AddressBlockUsage::Buffer,
+            Some("reserved") => AddressBlockUsage::Reserved,
+            _ => AddressBlockUsage::Registers,
+        };
+        let width = text(block, "width").map(parse_value).transpose()?;
+        Ok(PeripheralInfo::builder()
+            .name(name)
+            .description(description(block))
+            .base_address(self.bytes(parse_value(text_req(block, "baseAddress")?)?))
+            .default_register_properties(
+                RegisterProperties::new()
+                    .size(width.map(|w| w as u32))
+                    .access(access(block)?),
+            )
+            .address_block(Some(vec![AddressBlock::builder()
+                .offset(0)
+                .size(range as u32)
+                .usage(usage)
+                .build(self.lvl)?]))
+            .registers(Some(self.items(block)?))
+            .build(self.lvl)?)
+    }
+
+    /// Registers and register files of an address block or register file
+    fn items(&self, parent: Node) -> Result<Vec<RegisterCluster>> {
+        let mut items = Vec::new();
+        for node in parent.children().filter(|n| n.is_element()) {
+            match node.tag_name().name() {
+                "register" => items.push(RegisterCluster::Register(
+                    self.register(node).with_context(|| {
+                        format!(
+                            "Error converting register `{}`",
+                            text(node, "name").unwrap_or("")
+                        )
+                    })?,
+                )),
+                "registerFile" => items.push(RegisterCluster::Cluster(
+                    self.register_file(node).with_context(|| {
+                        format!(
+                            "Error converting register file `{}`",
+                            text(node, "name").unwrap_or("")
+                        )
+                    })?,
+                )),
+                _ => {}
+            }
+        }
+        Ok(items)
+    }
+
+    fn register_file(&self, node: Node) -> Result<crate::svd::Cluster> {
+        let range = self.bytes(parse_value(text_req(node, "range")?)?) as u32;
+        let (name, dim) = self.dim(node, range)?;
+        Ok(ClusterInfo::builder()
+            .name(name)
+            .description(description(node))
+            .address_offset(self.bytes(parse_value(text_req(node, "addressOffset")?)?) as u32)
+            .children(self.items(node)?)
+            .build(self.lvl)?
+            .maybe_array(dim))
+    }
+
+    fn register(&self, node: Node) -> Result<crate::svd::Register> {
+        let size = parse_value(text_req(node, "size")?)? as u32;
+        // Elements of register arrays start at the next address
+        let (name, dim) = self.dim(node, (size + self.unit - 1) / self.unit * self.unit / 8)?;
+        let fields = children(node, "field")
+            .map(|f| {
+                self.field(f).with_context(|| {
+                    format!("Error converting field `{}`", text(f, "name").unwrap_or(""))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // IP-XACT 2009 gives the reset of the whole register, 2014 the one of each field
+        let (reset_value, reset_mask) = match child(node, "reset") {
+            Some(reset) => (
+                Some(parse_value(text_req(reset, "value")?)?),
+                text(reset, "mask").map(parse_value).transpose()?,
+            ),
+            None => {
+                let mut value = 0;
+                let mut mask = 0;
+                for (field, reset) in &fields {
+                    if let Some(reset) = reset {
+                        value |= reset << field.bit_offset();
+                        mask |= field.bitmask();
+                    }
+                }
+                if mask == 0 {
+                    (None, None)
+                } else {
+                    (Some(value), Some(mask))
+                }
+            }
+        };
+        let fields = fields.into_iter().map(|(f, _)| f).collect::<Vec<_>>();
+
+        Ok(RegisterInfo::builder()
+            .name(name)
+            .description(description(node))
+            .address_offset(self.bytes(parse_value(text_req(node, "addressOffset")?)?) as u32)
+            .size(Some(size))
+            .access(access(node)?)
+            .reset_value(reset_value)
+            .reset_mask(reset_mask)
+            .fields((!fields.is_empty()).then(|| fields))
+            .build(self.lvl)?
+            .maybe_array(dim))
+    }
+
+    /// Field with its reset value
+    fn field(&self, node: Node) -> Result<(Field, Option<u64>)> {
+        let width = parse_value(text_req(node, "bitWidth")?)? as u32;
+        let modified_write_values = text(node, "modifiedWriteValue")
+            .map(|s| {
+                ModifiedWriteValues::parse_str(s)
+                    .ok_or_else(|| anyhow!("Unknown modifiedWriteValue `{}`", s))
+            })
+            .transpose()?;
+        let read_action = text(node, "readAction")
+            .map(|s| ReadAction::parse_str(s).ok_or_else(|| anyhow!("Unknown readAction `{}`", s)))
+            .transpose()?;
+        let write_constraint = match child(node, "writeValueConstraint") {
+            Some(c) => Some(if let Some(min) = text(c, "minimum") {
+                WriteConstraint::Range(WriteConstraintRange {
+                    min: parse_value(min)?,
+                    max: parse_value(text_req(c, "maximum")?)?,
+                })
+            } else if child(c, "useEnumeratedValues").is_some() {
+                WriteConstraint::UseEnumeratedValues(true)
+            } else {
+                WriteConstraint::WriteAsRead(true)
+            }),
+            None => None,
+        };
+        let reset = child(node, "resets")
+            .and_then(|r| child(r, "reset"))
+            .map(|r| text_req(r, "value").and_then(parse_value))
+            .transpose()?;
+
+        let field = FieldInfo::builder()
+            .name(text_req(node, "name")?.to_string())
+            .description(description(node))
+            .bit_range(BitRange::from_offset_width(
+                parse_value(text_req(node, "bitOffset")?)? as u32,
+                width,
+            ))
+            .access(access(node)?)
+            .modified_write_values(modified_write_values)
+            .read_action(read_action)
+            .write_constraint(write_constraint)
+            .enumerated_values(self.enumerated_values(node)?)
+            .build(self.lvl)?
+            .single();
+        Ok((field, reset))
+    }
+
+    fn enumerated_values(&self, field: Node) -> Result<Vec<EnumeratedValues>> {
+        let values = match child(field, "enumeratedValues") {
+            Some(values) => values,
+            None => return Ok(Vec::new()),
+        };
+        // SVD groups values by usage where IP-XACT sets it on each value
+        let mut groups: Vec<(Option<Usage>, Vec<EnumeratedValue>)> = Vec::new();
+        for value in children(values, "enumeratedValue") {
+            let usage = match value.attribute("usage") {
+                Some("read") => Some(Usage::Read),
+                Some("write") => Some(Usage::Write),
+                _ => None,
+            };
+            let value = EnumeratedValue::builder()
+                .name(text_req(value, "name")?.to_string())
+                .description(description(value))
+                .value(Some(parse_value(text_req(value, "value")?)?))
+                .build(self.lvl)?;
+            match groups.iter_mut().find(|(u, _)| *u == usage) {
+                Some((_, values)) => values.push(value),
+                None => groups.push((usage, vec![value])),
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(usage, values)| {
+                Ok(EnumeratedValues::builder()
+                    .usage(usage)
+                    .values(values)
+                    .build(self.lvl)?)
+            })
+            .collect()
+    }
+
+    /// Name of an element that may be an array and its `DimElement`
+    fn dim(&self, node: Node, increment: u32) -> Result<(String, Option<DimElement>)> {
+        let name = text_req(node, "name")?;
+        let dims = children(node, "dim")
+            .map(|d| d.text().map(parse_value).unwrap_or(Ok(1)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(match dims.len() {
+            0 => (name.to_string(), None),
+            n => {
+                if n > 1 {
+                    warn!("{} has {} dimensions, flattening them", name, n);
+                }
+                (
+                    format!("{}[%s]", name),
+                    Some(
+                        DimElement::builder()
+                            .dim(dims.iter().product::<u64>() as u32)
+                            .dim_increment(increment)
+                            .build(self.lvl)?,
+                    ),
+                )
+            }
+        })
+    }
+}
+
+fn description(node: Node) -> Option<String> {
+    text(node, "description").map(String::from)
+}
+
+fn access(node: Node) -> Result<Option<Access>> {
+    text(node, "access")
+        .map(|s| Access::parse_str(s).ok_or_else(|| anyhow!("Unknown access `{}`", s)))
+        .transpose()
+}
+
+/// Parses a decimal, `0x` prefixed or Verilog style literal
+fn parse_value(s: &str) -> Result<u64> {
+    let s = s.trim();
+    let pos = match s.find('\'') {
+        Some(pos) => pos,
+        None => return parse_int(s),
+    };
+    let mut chars = s[pos + 1..].trim_start_matches(['s', 'S']).chars();
+    let radix = match chars.next() {
+        Some('h' | 'H') => 16,
+        Some('d' | 'D') => 10,
+        Some('o' | 'O') => 8,
+        Some('b' | 'B') => 2,
+        _ => bail!("`{}` is not a valid literal", s),
+    };
+    u64::from_str_radix(&chars.as_str().replace('_', ""), radix)
+        .with_context(|| format!("`{}` is not a valid literal", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(unit: u32) -> String {
+        format!(
+            r#"<ipxact:component xmlns:ipxact="http://www.accellera.org/XMLSchema/IPXACT/1685-2014">
+  <ipxact:name>ip</ipxact:name>
+  <ipxact:memoryMaps>
+    <ipxact:memoryMap>
+      <ipxact:name>regs</ipxact:name>
+      <ipxact:addressUnitBits>{unit}</ipxact:addressUnitBits>
+      <ipxact:addressBlock>
+        <ipxact:name>BLOCK</ipxact:name>
+        <ipxact:baseAddress>0x100</ipxact:baseAddress>
+        <ipxact:range>0x10</ipxact:range>
+        <ipxact:width>32</ipxact:width>
+        <ipxact:register>
+          <ipxact:name>CTRL</ipxact:name>
+          <ipxact:addressOffset>0x0</ipxact:addressOffset>
+          <ipxact:size>32</ipxact:size>
+          <ipxact:field>
+            <ipxact:name>EN</ipxact:name>
+            <ipxact:bitOffset>0</ipxact:bitOffset>
+            <ipxact:resets><ipxact:reset><ipxact:value>1</ipxact:value></ipxact:reset></ipxact:resets>
+            <ipxact:bitWidth>1</ipxact:bitWidth>
+          </ipxact:field>
+        </ipxact:register>
+        <ipxact:register>
+          <ipxact:name>DATA</ipxact:name>
+          <ipxact:dim>4</ipxact:dim>
+          <ipxact:addressOffset>0x1</ipxact:addressOffset>
+          <ipxact:size>8</ipxact:size>
+        </ipxact:register>
+      </ipxact:addressBlock>
+    </ipxact:memoryMap>
+  </ipxact:memoryMaps>
+</ipxact:component>"#
+        )
+    }
+
+    fn registers(device: &Device) -> Vec<&RegisterInfo> {
+        device.peripherals[0]
+            .registers()
+            .map(|r| match r {
+                crate::svd::Register::Single(info) | crate::svd::Register::Array(info, _) => info,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn byte_addresses() {
+        let device = parse(&component(8), ValidateLevel::Disabled).unwrap();
+        assert_eq!(device.peripherals[0].base_address, 0x100);
+        let regs = registers(&device);
+        assert_eq!(regs[0].properties.reset_value, Some(1));
+        assert_eq!(regs[1].address_offset, 1);
+        match &device.peripherals[0].registers().nth(1).unwrap() {
+            crate::svd::Register::Array(_, dim) => assert_eq!(dim.dim_increment, 1),
+            _ => panic!("DATA is not an array"),
+        }
+    }
+
+    #[test]
+    fn word_addresses() {
+        let device = parse(&component(32), ValidateLevel::Disabled).unwrap();
+        assert_eq!(device.peripherals[0].base_address, 0x400);
+        let regs = registers(&device);
+        assert_eq!(regs[1].address_offset, 4);
+        match &device.peripherals[0].registers().nth(1).unwrap() {
+            crate::svd::Register::Array(_, dim) => assert_eq!(dim.dim_increment, 4),
+            _ => panic!("DATA is not an array"),
+        }
+    }
+
+    #[test]
+    fn odd_address_unit() {
+        assert!(parse(&component(12), ValidateLevel::Disabled).is_err());
+        assert!(parse(&component(0), ValidateLevel::Disabled).is_err());
+    }
+
+    #[test]
+    fn not_a_component() {
+        assert!(is_component(
+            "<?xml version=\"1.0\"?><!-- c --><spirit:component/>"
+        ));
+        assert!(!is_component("<device/>"));
+        assert!(parse("<device/>", ValidateLevel::Disabled).is_err());
+    }
+}
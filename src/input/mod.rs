@@ -0,0 +1,17 @@
+//! Converters from non-SVD register description formats into [`svd::Device`](crate::svd::Device)
+//! and reading of the don't-care patterns of SVD files
+
+#[cfg(feature = "atdf")]
+pub mod atdf;
+
+pub mod dont_care;
+
+#[cfg(feature = "ipxact")]
+pub mod ipxact;
+
+#[cfg(feature = "systemrdl")]
+pub mod systemrdl;
+
+#[cfg(any(feature = "atdf", feature = "ipxact"))]
+#[cfg_attr(not(all(feature = "atdf", feature = "ipxact")), allow(dead_code))]
+mod xml;
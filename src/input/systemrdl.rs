@@ -0,0 +1,1123 @@
+//! Conversion of SystemRDL register descriptions
+//!
+//! Only the elaborated subset of SystemRDL 2.0 is understood: `addrmap`, `regfile`, `reg` and
+//! `field` definitions and instances (named or anonymous, arrays, `@`, `+=` and `%=`), `enum`
+//! encodings, `default` and dynamic (`inst->prop`) property assignments. Preprocessing,
+//! parameters, signals, memories and user defined properties are not supported.
+//!
+//! The last root `addrmap` that is not instantiated anywhere is the device. Every `addrmap`
+//! instantiated in it becomes a peripheral, registers and register files placed directly in the
+//! device are gathered in a peripheral named after it. Instances without an address are placed
+//! after the previous one, aligned to their size rounded up to a power of two. `hw` access has no
+//! SVD equivalent and is ignored.
+
+use crate::svd::{
+    Access, AddressBlock, AddressBlockUsage, BitRange, Cluster, ClusterInfo, Device, DimElement,
+    EnumeratedValue, EnumeratedValues, Field, FieldInfo, ModifiedWriteValues, Peripheral,
+    PeripheralInfo, ReadAction, Register, RegisterCluster, RegisterInfo, RegisterProperties,
+    ValidateLevel,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use log::warn;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Parses a SystemRDL description into a [`Device`]
+pub fn parse(input: &str, lvl: ValidateLevel) -> Result<Device> {
+    let root = Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+    }
+    .body(false)?;
+    let top = top(&root)?;
+    let scopes = vec![&root, &top.body];
+    let ctx = Ctx { lvl };
+
+    let (placed, end) = place(&scopes)?;
+    let mut peripherals = Vec::new();
+    let mut registers = Vec::new();
+    for p in &placed {
+        match p.elem.comp.kind {
+            Kind::AddrMap => peripherals.push(
+                ctx.peripheral(p)
+                    .with_context(|| format!("Error converting addrmap `{}`", p.elem.inst.name))?,
+            ),
+            Kind::Reg => registers.push(RegisterCluster::Register(ctx.register(p)?)),
+            _ => registers.push(RegisterCluster::Cluster(ctx.cluster(p)?)),
+        }
+    }
+
+    let name = top.name.clone().unwrap_or_default();
+    let top_props = top
+        .body
+        .props
+        .iter()
+        .map(|(n, v)| (n.as_str(), v))
+        .collect::<Props>();
+    if !registers.is_empty() {
+        // Registers of the device itself are gathered in a peripheral starting at the first one
+        let base = placed
+            .iter()
+            .filter(|p| p.elem.comp.kind != Kind::AddrMap)
+            .map(|p| p.offset)
+            .min()
+            .unwrap_or(0);
+        for rc in &mut registers {
+            match rc {
+                RegisterCluster::Register(r) => r.address_offset -= base as u32,
+                RegisterCluster::Cluster(c) => c.address_offset -= base as u32,
+            }
+        }
+        peripherals.insert(
+            0,
+            PeripheralInfo::builder()
+                .name(name.clone())
+                .description(description(&top_props))
+                .base_address(base)
+                .address_block(Some(vec![ctx.address_block(end - base)?]))
+                .registers(Some(registers))
+                .build(lvl)?
+                .single(),
+        );
+    }
+
+    Ok(Device::builder()
+        .name(name)
+        .version("1.0".to_string())
+        .description(description(&top_props).unwrap_or_default())
+        .address_unit_bits(8)
+        .width(32)
+        .default_register_properties(
+            RegisterProperties::new()
+                .size(Some(32))
+                .access(Some(Access::ReadWrite))
+                .reset_value(Some(0))
+                .reset_mask(Some(0xffff_ffff)),
+        )
+        .peripherals(peripherals)
+        .build(lvl)?)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Num(u64),
+    Str(String),
+    Punct(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Ident(s) => write!(f, "`{}`", s),
+            Self::Num(n) => write!(f, "`{}`", n),
+            Self::Str(s) => write!(f, "\"{}\"", s),
+            Self::Punct(p) => write!(f, "`{}`", p),
+        }
+    }
+}
+
+/// Splits `input` into tokens tagged with their line number
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>> {
+    const PUNCTS: &[&str] = &[
+        "->", "+=", "%=", "{", "}", "[", "]", "(", ")", ";", ",", "=", "@", ":", ".", "#",
+    ];
+    let bytes = input.as_bytes();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let rest = &input[i..];
+        if c == b'\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_ascii_whitespace() {
+            i += 1;
+        } else if rest.starts_with("//") {
+            i += rest.find('\n').unwrap_or(rest.len());
+        } else if rest.starts_with("/*") {
+            let end = rest
+                .find("*/")
+                .ok_or_else(|| anyhow!("line {}: unterminated comment", line))?;
+            line += rest[..end].matches('\n').count();
+            i += end + 2;
+        } else if c == b'"' {
+            let mut j = i + 1;
+            while j < bytes.len() && bytes[j] != b'"' {
+                j += if bytes[j] == b'\\' { 2 } else { 1 };
+            }
+            if j >= bytes.len() {
+                bail!("line {}: unterminated string", line);
+            }
+            let s = &input[i + 1..j];
+            tokens.push((
+                Token::Str(s.replace("\\\"", "\"").replace("\\\\", "\\")),
+                line,
+            ));
+            line += s.matches('\n').count();
+            i = j + 1;
+        } else if c.is_ascii_alphabetic() || c == b'_' || c == b'\\' {
+            let start = if c == b'\\' { i + 1 } else { i };
+            let len = input[start..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(input.len() - start);
+            tokens.push((Token::Ident(input[start..start + len].to_string()), line));
+            i = start + len;
+        } else if c.is_ascii_digit() || c == b'\'' {
+            let (value, len) = number(rest).with_context(|| format!("line {}", line))?;
+            tokens.push((Token::Num(value), line));
+            i += len;
+        } else if c == b'`' {
+            bail!("line {}: preprocessor directives are not supported", line);
+        } else if rest.starts_with("<%") {
+            bail!("line {}: embedded Perl is not supported", line);
+        } else if let Some(p) = PUNCTS.iter().find(|p| rest.starts_with(**p)) {
+            tokens.push((Token::Punct(p), line));
+            i += p.len();
+        } else {
+            bail!(
+                "line {}: unexpected character `{}`",
+                line,
+                rest.chars().next().unwrap()
+            );
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parses a decimal, `0x` prefixed or Verilog style number at the start of `s`, returns its value
+/// and length
+fn number(s: &str) -> Result<(u64, usize)> {
+    let digits_len = |s: &str| {
+        s.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(s.len())
+    };
+    let parse = |digits: &str, radix| {
+        u64::from_str_radix(&digits.replace('_', ""), radix)
+            .with_context(|| format!("`{}` is not a valid number", digits))
+    };
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        let len = digits_len(hex);
+        return Ok((parse(&hex[..len], 16)?, len + 2));
+    }
+    let width = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '_'))
+        .unwrap_or(s.len());
+    match s[width..].strip_prefix('\'') {
+        Some(literal) => {
+            let radix = match literal.chars().next() {
+                Some('h' | 'H') => 16,
+                Some('d' | 'D') => 10,
+                Some('o' | 'O') => 8,
+                Some('b' | 'B') => 2,
+                _ => bail!("`{}` is not a valid number", &s[..width + 1]),
+            };
+            let len = digits_len(&literal[1..]);
+            Ok((parse(&literal[1..len + 1], radix)?, width + len + 2))
+        }
+        None => Ok((parse(&s[..width], 10)?, width)),
+    }
+}
+
+#[derive(Debug)]
+enum Value {
+    Num(u64),
+    Bool(bool),
+    Str(String),
+    Ident(String),
+}
+
+impl Value {
+    fn num(&self) -> Option<u64> {
+        match self {
+            Self::Num(n) => Some(*n),
+            Self::Bool(b) => Some(*b as u64),
+            _ => None,
+        }
+    }
+
+    fn str(&self) -> Option<&str> {
+        match self {
+            Self::Str(s) | Self::Ident(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn flag(&self) -> bool {
+        self.num() != Some(0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    AddrMap,
+    RegFile,
+    Reg,
+    Field,
+}
+
+#[derive(Debug)]
+struct Component {
+    kind: Kind,
+    name: Option<String>,
+    body: Body,
+}
+
+#[derive(Debug)]
+enum TypeRef {
+    /// Named component defined in an enclosing scope
+    Named(String),
+    /// Component defined along with the instance, index in `defs` of the same body
+    Def(usize),
+}
+
+#[derive(Debug)]
+struct Inst {
+    ty: TypeRef,
+    name: String,
+    /// Array dimensions, or field width
+    dims: Vec<u64>,
+    /// Field bit range
+    range: Option<(u64, u64)>,
+    reset: Option<u64>,
+    addr: Option<u64>,
+    stride: Option<u64>,
+    align: Option<u64>,
+}
+
+#[derive(Debug)]
+struct EnumDef {
+    name: String,
+    entries: Vec<EnumEntry>,
+}
+
+#[derive(Debug)]
+struct EnumEntry {
+    name: String,
+    value: u64,
+    props: Vec<(String, Value)>,
+}
+
+#[derive(Debug, Default)]
+struct Body {
+    defs: Vec<Component>,
+    enums: Vec<EnumDef>,
+    props: Vec<(String, Value)>,
+    defaults: Vec<(String, Value)>,
+    /// `target->prop = value` assignments
+    dynamic: Vec<(String, String, Value)>,
+    insts: Vec<Inst>,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos.min(self.tokens.len().saturating_sub(1)))
+            .map_or(0, |(_, l)| *l)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| anyhow!("unexpected end of file"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Punct(p)) if *p == punct);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<()> {
+        if !self.eat(punct) {
+            bail!(
+                "line {}: expected `{}`, found {}",
+                self.line(),
+                punct,
+                self.describe_next()
+            );
+        }
+        Ok(())
+    }
+
+    fn describe_next(&self) -> String {
+        self.peek()
+            .map_or_else(|| "end of file".to_string(), ToString::to_string)
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.peek() {
+            Some(Token::Ident(s)) => {
+                let s = s.clone();
+                self.pos += 1;
+                Ok(s)
+            }
+            _ => bail!(
+                "line {}: expected identifier, found {}",
+                self.line(),
+                self.describe_next()
+            ),
+        }
+    }
+
+    fn number(&mut self) -> Result<u64> {
+        match self.peek() {
+            Some(Token::Num(n)) => {
+                let n = *n;
+                self.pos += 1;
+                Ok(n)
+            }
+            _ => bail!(
+                "line {}: expected number, found {}",
+                self.line(),
+                self.describe_next()
+            ),
+        }
+    }
+
+    /// Value of a property assignment, a property alone is set to `true`
+    fn value(&mut self) -> Result<Value> {
+        if !self.eat("=") {
+            return Ok(Value::Bool(true));
+        }
+        let line = self.line();
+        Ok(match self.next()? {
+            Token::Num(n) => Value::Num(n),
+            Token::Str(s) => Value::Str(s),
+            Token::Ident(s) if s == "true" => Value::Bool(true),
+            Token::Ident(s) if s == "false" => Value::Bool(false),
+            Token::Ident(s) => Value::Ident(s),
+            t => bail!("line {}: expected property value, found {}", line, t),
+        })
+    }
+
+    /// Parses the items of a component body up to its closing brace, or the whole file
+    fn body(&mut self, braced: bool) -> Result<Body> {
+        let mut body = Body::default();
+        loop {
+            if braced && self.eat("}") {
+                break;
+            }
+            if !braced && self.peek().is_none() {
+                break;
+            }
+            let line = self.line();
+            let word = match self.next()? {
+                Token::Ident(word) => word,
+                t => bail!("line {}: unexpected {}", line, t),
+            };
+            match word.as_str() {
+                "external" | "internal" => {}
+                "addrmap" => self.component(Kind::AddrMap, &mut body)?,
+                "regfile" => self.component(Kind::RegFile, &mut body)?,
+                "reg" => self.component(Kind::Reg, &mut body)?,
+                "field" => self.component(Kind::Field, &mut body)?,
+                "enum" => body.enums.push(self.enum_def()?),
+                "default" => {
+                    let name = self.ident()?;
+                    let value = self.value()?;
+                    self.expect(";")?;
+                    body.defaults.push((name, value));
+                }
+                "property" | "signal" | "mem" | "constraint" | "struct" => {
+                    warn!("line {}: skipping unsupported `{}`", line, word);
+                    self.skip_statement()?;
+                }
+                _ => {
+                    if self.eat("->") {
+                        let prop = self.ident()?;
+                        let value = self.value()?;
+                        self.expect(";")?;
+                        body.dynamic.push((word, prop, value));
+                    } else if matches!(self.peek(), Some(Token::Ident(_))) {
+                        self.instances(TypeRef::Named(word), &mut body)?;
+                    } else if self.eat(".") {
+                        bail!("line {}: hierarchical assignments are not supported", line);
+                    } else {
+                        let value = self.value()?;
+                        self.expect(";")?;
+                        body.props.push((word, value));
+                    }
+                }
+            }
+        }
+        Ok(body)
+    }
+
+    fn component(&mut self, kind: Kind, body: &mut Body) -> Result<()> {
+        let name = match self.peek() {
+            Some(Token::Ident(_)) => Some(self.ident()?),
+            _ => None,
+        };
+        if self.eat("#") {
+            bail!(
+                "line {}: parameterized components are not supported",
+                self.line()
+            );
+        }
+        self.expect("{")?;
+        let inner = self.body(true)?;
+        body.defs.push(Component {
+            kind,
+            name,
+            body: inner,
+        });
+        if self.eat(";") {
+            return Ok(());
+        }
+        self.instances(TypeRef::Def(body.defs.len() - 1), body)
+    }
+
+    fn instances(&mut self, ty: TypeRef, body: &mut Body) -> Result<()> {
+        let mut ty = Some(ty);
+        loop {
+            if matches!(self.peek(), Some(Token::Ident(s)) if s == "external" || s == "internal") {
+                self.pos += 1;
+            }
+            let name = self.ident()?;
+            let ty = match ty.take() {
+                Some(ty) => ty,
+                // Further instances of the list share the type of the first one
+                None => match &body.insts.last().unwrap().ty {
+                    TypeRef::Named(n) => TypeRef::Named(n.clone()),
+                    TypeRef::Def(i) => TypeRef::Def(*i),
+                },
+            };
+            let mut inst = Inst {
+                ty,
+                name,
+                dims: Vec::new(),
+                range: None,
+                reset: None,
+                addr: None,
+                stride: None,
+                align: None,
+            };
+            while self.eat("[") {
+                let a = self.number()?;
+                if self.eat(":") {
+                    inst.range = Some((a, self.number()?));
+                } else {
+                    inst.dims.push(a);
+                }
+                self.expect("]")?;
+            }
+            if self.eat("=") {
+                inst.reset = Some(self.number()?);
+            }
+            if self.eat("@") {
+                inst.addr = Some(self.number()?);
+            }
+            if self.eat("+=") {
+                inst.stride = Some(self.number()?);
+            }
+            if self.eat("%=") {
+                inst.align = Some(self.number()?);
+            }
+            body.insts.push(inst);
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect(";")
+    }
+
+    fn enum_def(&mut self) -> Result<EnumDef> {
+        let name = self.ident()?;
+        self.expect("{")?;
+        let mut entries = Vec::new();
+        let mut next = 0;
+        while !self.eat("}") {
+            let entry = self.ident()?;
+            let value = if self.eat("=") { self.number()? } else { next };
+            next = value + 1;
+            let mut props = Vec::new();
+            if self.eat("{") {
+                while !self.eat("}") {
+                    let prop = self.ident()?;
+                    let value = self.value()?;
+                    self.expect(";")?;
+                    props.push((prop, value));
+                }
+            }
+            self.expect(";")?;
+            entries.push(EnumEntry {
+                name: entry,
+                value,
+                props,
+            });
+        }
+        self.expect(";")?;
+        Ok(EnumDef { name, entries })
+    }
+
+    /// Skips tokens up to the end of the current statement
+    fn skip_statement(&mut self) -> Result<()> {
+        let mut depth = 0;
+        loop {
+            match self.next()? {
+                Token::Punct("{") => depth += 1,
+                Token::Punct("}") => depth -= 1,
+                Token::Punct(";") if depth == 0 => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The last root `addrmap` that is not instantiated by other components
+fn top(root: &Body) -> Result<&Component> {
+    fn used<'a>(body: &'a Body, names: &mut HashSet<&'a str>) {
+        for inst in &body.insts {
+            if let TypeRef::Named(name) = &inst.ty {
+                names.insert(name);
+            }
+        }
+        for def in &body.defs {
+            used(&def.body, names);
+        }
+    }
+    let mut names = HashSet::new();
+    used(root, &mut names);
+    root.defs
+        .iter()
+        .rev()
+        .find(|c| {
+            c.kind == Kind::AddrMap && c.name.as_deref().map_or(false, |n| !names.contains(n))
+        })
+        .ok_or_else(|| anyhow!("No top level `addrmap` found"))
+}
+
+type Props<'a> = HashMap<&'a str, &'a Value>;
+
+/// An instance with its resolved component and properties
+struct Elem<'a> {
+    inst: &'a Inst,
+    comp: &'a Component,
+    /// Lexical scopes of the component body, innermost last
+    scopes: Vec<&'a Body>,
+    props: Props<'a>,
+}
+
+fn elem<'a>(scopes: &[&'a Body], inst: &'a Inst) -> Result<Elem<'a>> {
+    let (comp, depth) = match &inst.ty {
+        TypeRef::Def(i) => (&scopes[scopes.len() - 1].defs[*i], scopes.len() - 1),
+        TypeRef::Named(name) => scopes
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(depth, body)| {
+                body.defs
+                    .iter()
+                    .rev()
+                    .find(|c| c.name.as_deref() == Some(name))
+                    .map(|c| (c, depth))
+            })
+            .ok_or_else(|| anyhow!("Unknown component type `{}`", name))?,
+    };
+
+    // Defaults of the scopes the component is defined in, then its own properties, then
+    // assignments to the instance
+    let mut props = Props::new();
+    for body in &scopes[..=depth] {
+        props.extend(body.defaults.iter().map(|(n, v)| (n.as_str(), v)));
+    }
+    props.extend(comp.body.props.iter().map(|(n, v)| (n.as_str(), v)));
+    props.extend(
+        scopes[scopes.len() - 1]
+            .dynamic
+            .iter()
+            .filter(|(target, _, _)| *target == inst.name)
+            .map(|(_, n, v)| (n.as_str(), v)),
+    );
+
+    let mut inner = scopes[..=depth].to_vec();
+    inner.push(&comp.body);
+    Ok(Elem {
+        inst,
+        comp,
+        scopes: inner,
+        props,
+    })
+}
+
+/// An instance with its address, relative to the start of the enclosing component
+struct Placed<'a> {
+    elem: Elem<'a>,
+    offset: u64,
+    stride: u64,
+    size: u64,
+}
+
+/// Places the instances of the innermost scope, returns them and the size they span
+fn place<'a>(scopes: &[&'a Body]) -> Result<(Vec<Placed<'a>>, u64)> {
+    let mut placed = Vec::new();
+    let mut next = 0;
+    let mut end = 0;
+    for inst in &scopes[scopes.len() - 1].insts {
+        let elem = elem(scopes, inst)?;
+        let size = match elem.comp.kind {
+            Kind::Field => bail!("Field `{}` is not in a register", inst.name),
+            Kind::Reg => u64::from(regwidth(&elem)? / 8),
+            _ => place(&elem.scopes)?.1,
+        };
+        let count = inst.dims.iter().product::<u64>();
+        if count == 0 {
+            bail!("Array `{}` has no elements", inst.name);
+        }
+        let stride = inst.stride.unwrap_or(size);
+        let offset = match inst.addr {
+            Some(addr) => addr,
+            None => {
+                let align = inst.align.unwrap_or_else(|| size.next_power_of_two());
+                if align == 0 {
+                    bail!("Alignment of `{}` is 0", inst.name);
+                }
+                (next + align - 1) / align * align
+            }
+        };
+        next = offset + stride * (count - 1) + size;
+        end = end.max(next);
+        placed.push(Placed {
+            elem,
+            offset,
+            stride,
+            size,
+        });
+    }
+    Ok((placed, end))
+}
+
+fn regwidth(elem: &Elem) -> Result<u32> {
+    let width = elem
+        .props
+        .get("regwidth")
+        .and_then(|v| v.num())
+        .unwrap_or(32);
+    if width < 8 || !width.is_power_of_two() {
+        bail!("Invalid regwidth {} of `{}`", width, elem.inst.name);
+    }
+    Ok(width as u32)
+}
+
+fn description(props: &Props) -> Option<String> {
+    props
+        .get("desc")
+        .or_else(|| props.get("name"))
+        .and_then(|v| v.str())
+        .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+struct Ctx {
+    lvl: ValidateLevel,
+}
+
+impl Ctx {
+    fn peripheral(&self, p: &Placed) -> Result<Peripheral> {
+        let (name, dim) = self.dim(p)?;
+        Ok(PeripheralInfo::builder()
+            .name(name)
+            .description(description(&p.elem.props))
+            .base_address(p.offset)
+            .address_block(Some(vec![self.address_block(p.size)?]))
+            .registers(Some(self.items(&p.elem.scopes)?))
+            .build(self.lvl)?
+            .maybe_array(dim))
+    }
+
+    fn address_block(&self, size: u64) -> Result<AddressBlock> {
+        Ok(AddressBlock::builder()
+            .offset(0)
+            .size(size as u32)
+            .usage(AddressBlockUsage::Registers)
+            .build(self.lvl)?)
+    }
+
+    /// Registers and register files of the innermost scope
+    fn items(&self, scopes: &[&Body]) -> Result<Vec<RegisterCluster>> {
+        place(scopes)?
+            .0
+            .iter()
+            .map(|p| {
+                Ok(match p.elem.comp.kind {
+                    Kind::Reg => RegisterCluster::Register(self.register(p)?),
+                    _ => RegisterCluster::Cluster(self.cluster(p)?),
+                })
+            })
+            .collect()
+    }
+
+    fn cluster(&self, p: &Placed) -> Result<Cluster> {
+        let (name, dim) = self.dim(p)?;
+        Ok(ClusterInfo::builder()
+            .name(name)
+            .description(description(&p.elem.props))
+            .address_offset(p.offset as u32)
+            .children(
+                self.items(&p.elem.scopes)
+                    .with_context(|| format!("Error converting `{}`", p.elem.inst.name))?,
+            )
+            .build(self.lvl)?
+            .maybe_array(dim))
+    }
+
+    fn register(&self, p: &Placed) -> Result<Register> {
+        let (name, dim) = self.dim(p)?;
+        let (fields, reset_value, reset_mask) = self
+            .fields(&p.elem)
+            .with_context(|| format!("Error converting register `{}`", p.elem.inst.name))?;
+        let access = if fields.iter().all(|f| f.access == Some(Access::ReadOnly)) {
+            Access::ReadOnly
+        } else if fields.iter().all(|f| f.access == Some(Access::WriteOnly)) {
+            Access::WriteOnly
+        } else {
+            Access::ReadWrite
+        };
+        Ok(RegisterInfo::builder()
+            .name(name)
+            .description(description(&p.elem.props))
+            .address_offset(p.offset as u32)
+            .size(Some(regwidth(&p.elem)?))
+            .access(Some(access))
+            .reset_value((reset_mask != 0).then(|| reset_value))
+            .reset_mask((reset_mask != 0).then(|| reset_mask))
+            .fields((!fields.is_empty()).then(|| fields))
+            .build(self.lvl)?
+            .maybe_array(dim))
+    }
+
+    /// Fields of a register with its reset value and mask
+    fn fields(&self, reg: &Elem) -> Result<(Vec<Field>, u64, u64)> {
+        let mut fields = Vec::new();
+        let mut reset_value = 0;
+        let mut reset_mask = 0;
+        let mut next = 0;
+        let regwidth = u64::from(regwidth(reg)?);
+        for inst in &reg.comp.body.insts {
+            let f = elem(&reg.scopes, inst)?;
+            if f.comp.kind != Kind::Field {
+                bail!("Only fields can be instantiated in a register");
+            }
+            let (lsb, width) = match inst.range {
+                Some((a, b)) => (a.min(b), a.max(b) - a.min(b) + 1),
+                None => (
+                    inst.addr.unwrap_or(next),
+                    inst.dims
+                        .first()
+                        .copied()
+                        .or_else(|| f.props.get("fieldwidth").and_then(|v| v.num()))
+                        .unwrap_or(1),
+                ),
+            };
+            if width == 0 || width > 64 || lsb + width > regwidth {
+                bail!(
+                    "Field `{}` of width {} at bit {} doesn't fit in a {} bit register",
+                    inst.name,
+                    width,
+                    lsb,
+                    regwidth
+                );
+            }
+            next = lsb + width;
+
+            let access = match f.props.get("sw").and_then(|v| v.str()).unwrap_or("rw") {
+                "rw" | "wr" => Access::ReadWrite,
+                "r" => Access::ReadOnly,
+                "w" => Access::WriteOnly,
+                "rw1" => Access::ReadWriteOnce,
+                "w1" => Access::WriteOnce,
+                "na" => {
+                    warn!("Skipping field {} not accessible by software", inst.name);
+                    continue;
+                }
+                sw => bail!("Unknown sw access `{}` of field `{}`", sw, inst.name),
+            };
+            let onwrite = match f.props.get("onwrite") {
+                Some(v) => v.str(),
+                None => ["woclr", "woset", "wot", "wzc", "wzs", "wzt", "wclr", "wset"]
+                    .into_iter()
+                    .find(|p| f.props.get(p).map_or(false, |v| v.flag())),
+            };
+            let modified_write_values = onwrite
+                .map(|w| {
+                    Ok(match w {
+                        "woclr" => ModifiedWriteValues::OneToClear,
+                        "woset" => ModifiedWriteValues::OneToSet,
+                        "wot" => ModifiedWriteValues::OneToToggle,
+                        "wzc" => ModifiedWriteValues::ZeroToClear,
+                        "wzs" => ModifiedWriteValues::ZeroToSet,
+                        "wzt" => ModifiedWriteValues::ZeroToToggle,
+                        "wclr" => ModifiedWriteValues::Clear,
+                        "wset" => ModifiedWriteValues::Set,
+                        "wuser" => ModifiedWriteValues::Modify,
+                        w => bail!("Unknown onwrite `{}` of field `{}`", w, inst.name),
+                    })
+                })
+                .transpose()?;
+            let onread = match f.props.get("onread") {
+                Some(v) => v.str(),
+                None => ["rclr", "rset"]
+                    .into_iter()
+                    .find(|p| f.props.get(p).map_or(false, |v| v.flag())),
+            };
+            let read_action = onread
+                .map(|r| {
+                    Ok(match r {
+                        "rclr" => ReadAction::Clear,
+                        "rset" => ReadAction::Set,
+                        "ruser" => ReadAction::Modify,
+                        r => bail!("Unknown onread `{}` of field `{}`", r, inst.name),
+                    })
+                })
+                .transpose()?;
+            let enumerated_values = match f.props.get("encode").and_then(|v| v.str()) {
+                Some(name) => vec![self.enumerated_values(&f.scopes, name)?],
+                None => Vec::new(),
+            };
+
+            if let Some(reset) = inst
+                .reset
+                .or_else(|| f.props.get("reset").and_then(|v| v.num()))
+            {
+                let mask = (u64::MAX >> (64 - width)) << lsb;
+                reset_value |= (reset << lsb) & mask;
+                reset_mask |= mask;
+            }
+            fields.push(
+                FieldInfo::builder()
+                    .name(inst.name.clone())
+                    .description(description(&f.props))
+                    .bit_range(BitRange::from_offset_width(lsb as u32, width as u32))
+                    .access(Some(access))
+                    .modified_write_values(modified_write_values)
+                    .read_action(read_action)
+                    .enumerated_values(enumerated_values)
+                    .build(self.lvl)?
+                    .single(),
+            );
+        }
+        Ok((fields, reset_value, reset_mask))
+    }
+
+    /// Name of an instance that may be an array and its `DimElement`
+    fn dim(&self, p: &Placed) -> Result<(String, Option<DimElement>)> {
+        let inst = p.elem.inst;
+        if inst.dims.is_empty() {
+            return Ok((inst.name.clone(), None));
+        }
+        if inst.dims.len() > 1 {
+            warn!(
+                "{} has {} dimensions, flattening them",
+                inst.name,
+                inst.dims.len()
+            );
+        }
+        Ok((
+            format!("{}[%s]", inst.name),
+            Some(
+                DimElement::builder()
+                    .dim(inst.dims.iter().product::<u64>() as u32)
+                    .dim_increment(p.stride as u32)
+                    .build(self.lvl)?,
+            ),
+        ))
+    }
+
+    fn enumerated_values(&self, scopes: &[&Body], name: &str) -> Result<EnumeratedValues> {
+        let def = scopes
+            .iter()
+            .rev()
+            .find_map(|body| body.enums.iter().find(|e| e.name == name))
+            .ok_or_else(|| anyhow!("Unknown enum `{}`", name))?;
+        let values = def
+            .entries
+            .iter()
+            .map(|entry| {
+                let props = entry.props.iter().map(|(n, v)| (n.as_str(), v)).collect();
+                Ok(EnumeratedValue::builder()
+                    .name(entry.name.clone())
+                    .description(description(&props))
+                    .value(Some(entry.value))
+                    .build(self.lvl)?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(EnumeratedValues::builder().values(values).build(self.lvl)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svd::{Register, RegisterCluster};
+
+    fn device(input: &str) -> Device {
+        parse(input, ValidateLevel::Disabled).unwrap()
+    }
+
+    fn error(input: &str) -> String {
+        format!("{:#}", parse(input, ValidateLevel::Disabled).unwrap_err())
+    }
+
+    fn register<'a>(children: &'a [RegisterCluster], name: &str) -> &'a Register {
+        children
+            .iter()
+            .find_map(|rc| match rc {
+                RegisterCluster::Register(r) if r.name == name => Some(r),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn placement_and_arrays() {
+        let d = device(
+            "addrmap top {
+                reg { field {} A[8]; } CTRL;
+                reg { regwidth = 16; field {} D[16]; } DATA[4] @ 0x10 += 4;
+                reg { field {} B; } LAST;
+            };",
+        );
+        assert_eq!(d.peripherals.len(), 1);
+        let regs = d.peripherals[0].registers.as_ref().unwrap();
+        assert_eq!(register(regs, "CTRL").address_offset, 0);
+        let data = register(regs, "DATA[%s]");
+        assert_eq!(data.address_offset, 0x10);
+        assert_eq!(data.properties.size, Some(16));
+        match data {
+            Register::Array(_, dim) => {
+                assert_eq!(dim.dim, 4);
+                assert_eq!(dim.dim_increment, 4);
+            }
+            _ => panic!("DATA is not an array"),
+        }
+        // After the last element, aligned to the register size
+        assert_eq!(register(regs, "LAST").address_offset, 0x20);
+    }
+
+    #[test]
+    fn peripherals_and_regfiles() {
+        let d = device(
+            "addrmap blk_t {
+                regfile {
+                    reg { field {} GO; } CMD;
+                    reg { field {} ARG[31:0]; } ARG;
+                } CH[2] @ 0x40;
+                CH->desc = \"Channel\";
+            };
+            addrmap top {
+                blk_t BLK0 @ 0x4000_0000;
+                blk_t BLK1[2] @ 0x4000_1000 += 0x1000;
+            };",
+        );
+        assert_eq!(d.peripherals.len(), 2);
+        assert_eq!(d.peripherals[0].name, "BLK0");
+        assert_eq!(d.peripherals[0].base_address, 0x4000_0000);
+        match &d.peripherals[1] {
+            Peripheral::Array(p, dim) => {
+                assert_eq!(p.name, "BLK1[%s]");
+                assert_eq!(dim.dim_increment, 0x1000);
+            }
+            _ => panic!("BLK1 is not an array"),
+        }
+        match &d.peripherals[0].registers.as_ref().unwrap()[0] {
+            RegisterCluster::Cluster(Cluster::Array(c, dim)) => {
+                assert_eq!(c.name, "CH[%s]");
+                assert_eq!(c.description.as_deref(), Some("Channel"));
+                assert_eq!(c.address_offset, 0x40);
+                assert_eq!(dim.dim_increment, 8);
+                assert_eq!(register(&c.children, "ARG").address_offset, 4);
+            }
+            _ => panic!("CH is not a cluster array"),
+        }
+    }
+
+    #[test]
+    fn enums_and_reset() {
+        let d = device(
+            "enum mode_e {
+                OFF = 2'd0 { desc = \"Disabled\"; };
+                TX;
+                RX = 3;
+            };
+            addrmap top {
+                reg {
+                    field { encode = mode_e; } MODE[5:4] = 2;
+                    field {} EN[0:0] = 1;
+                } CTRL;
+            };",
+        );
+        let ctrl = register(d.peripherals[0].registers.as_ref().unwrap(), "CTRL");
+        assert_eq!(ctrl.properties.reset_value, Some(0x21));
+        assert_eq!(ctrl.properties.reset_mask, Some(0x31));
+        let mode = &ctrl.fields.as_ref().unwrap()[0];
+        let values = &mode.enumerated_values[0].values;
+        let values = values
+            .iter()
+            .map(|v| (v.name.as_str(), v.value))
+            .collect::<Vec<_>>();
+        assert_eq!(values, [("OFF", Some(0)), ("TX", Some(1)), ("RX", Some(3))]);
+        assert_eq!(
+            mode.enumerated_values[0].values[0].description.as_deref(),
+            Some("Disabled")
+        );
+    }
+
+    #[test]
+    fn side_effects() {
+        let d = device(
+            "addrmap top {
+                reg {
+                    field { woclr; } ERR;
+                    field { sw = r; rclr; } RXNE;
+                    field { onwrite = wot; } TGL;
+                    field { sw = na; } HIDDEN;
+                } STATUS;
+            };",
+        );
+        let status = register(d.peripherals[0].registers.as_ref().unwrap(), "STATUS");
+        let fields = status.fields.as_ref().unwrap();
+        assert_eq!(fields.len(), 3);
+        assert_eq!(
+            fields[0].modified_write_values,
+            Some(ModifiedWriteValues::OneToClear)
+        );
+        assert_eq!(fields[1].access, Some(Access::ReadOnly));
+        assert_eq!(fields[1].read_action, Some(ReadAction::Clear));
+        assert_eq!(
+            fields[2].modified_write_values,
+            Some(ModifiedWriteValues::OneToToggle)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert!(
+            error("addrmap top { reg { field { reset = 1; } a[0]; } R; };").contains("doesn't fit")
+        );
+        assert!(error("addrmap top { reg { field {} a[70]; } R; };").contains("doesn't fit"));
+        assert!(error("addrmap top { reg { field {} a[35:0]; } R; };").contains("doesn't fit"));
+        assert!(error("addrmap top { reg { field {} a; } R %= 0; };").contains("Alignment"));
+        assert!(error("addrmap top { reg { field {} a; } R[0]; };").contains("no elements"));
+        assert!(error("addrmap top { foo_t X; };").contains("Unknown component type"));
+        assert!(error("addrmap top { reg { field { sw = x; } a; } R; };").contains("sw access"));
+        assert!(error("addrmap top { desc = \"open; };").contains("unterminated string"));
+        assert!(error("`include \"a.rdl\"").contains("preprocessor"));
+        assert!(error("reg r_t { field {} a; };").contains("No top level"));
+        assert!(error("addrmap top { reg { field {} a; } R").contains("end of file"));
+    }
+}
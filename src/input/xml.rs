@@ -0,0 +1,62 @@
+//! Helpers shared by the XML based converters
+
+use anyhow::{anyhow, Context, Result};
+use roxmltree::Node;
+
+/// First child element named `tag`
+pub fn child<'a, 'i>(node: Node<'a, 'i>, tag: &str) -> Option<Node<'a, 'i>> {
+    node.children()
+        .find(|c| c.is_element() && c.tag_name().name() == tag)
+}
+
+/// All child elements named `tag`
+pub fn children<'a, 'i: 'a>(
+    node: Node<'a, 'i>,
+    tag: &'a str,
+) -> impl Iterator<Item = Node<'a, 'i>> + 'a {
+    node.children()
+        .filter(move |c| c.is_element() && c.tag_name().name() == tag)
+}
+
+/// Trimmed text of the first child element named `tag`, if not empty
+pub fn text<'a>(node: Node<'a, '_>, tag: &str) -> Option<&'a str> {
+    child(node, tag)
+        .and_then(|c| c.text())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+}
+
+/// Text of the required child element `tag`
+pub fn text_req<'a>(node: Node<'a, '_>, tag: &str) -> Result<&'a str> {
+    text(node, tag).ok_or_else(|| anyhow!("`{}` has no `{}` element", node.tag_name().name(), tag))
+}
+
+/// Value of the required attribute `name`
+pub fn attr<'a>(node: Node<'a, '_>, name: &str) -> Result<&'a str> {
+    node.attribute(name)
+        .ok_or_else(|| anyhow!("`{}` has no `{}` attribute", node.tag_name().name(), name))
+}
+
+/// Parses a decimal or `0x` prefixed hexadecimal integer
+pub fn parse_int(s: &str) -> Result<u64> {
+    let s = s.trim();
+    let (digits, radix) = if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        (hex, 16)
+    } else {
+        (s, 10)
+    };
+    u64::from_str_radix(&digits.replace('_', ""), radix)
+        .with_context(|| format!("`{}` is not a valid integer", s))
+}
+
+/// Splits `mask` into `(offset, width)` runs of contiguous set bits, from the lowest
+pub fn mask_runs(mut mask: u64) -> Vec<(u32, u32)> {
+    let mut runs = Vec::new();
+    while mask != 0 {
+        let offset = mask.trailing_zeros();
+        let width = (mask >> offset).trailing_ones();
+        runs.push((offset, width));
+        mask &= !(((1u128 << width) - 1) as u64) << offset;
+    }
+    runs
+}
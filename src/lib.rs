@@ -18,14 +18,46 @@
 //!
 //! # Usage
 //!
-//! `svd2rust` supports Cortex-M, MSP430, RISCV and Xtensa LX6 microcontrollers. The generated crate can
-//! be tailored for either architecture using the `--target` flag. The flag accepts "cortex-m",
-//! "msp430", "riscv", "xtensa-lx" and "none" as values. "none" can be used to generate a crate that's
-//! architecture agnostic and that should work for architectures that `svd2rust` doesn't currently
-//! know about like the Cortex-A architecture.
+//! `svd2rust` supports Cortex-M, MSP430, RISCV, Xtensa LX6, MIPS and AVR microcontrollers. The
+//! generated crate can be tailored for either architecture using the `--target` flag. The flag
+//! accepts "cortex-m", "msp430", "riscv", "xtensa-lx", "mips", "avr" and "none" as values. "none"
+//! can be used to generate a crate that's architecture agnostic and that should work for
+//! architectures that `svd2rust` doesn't currently know about like the Cortex-A architecture.
 //!
 //! If the `--target` flag is omitted `svd2rust` assumes the target is the Cortex-M architecture.
 //!
+//! Besides SVD XML, the device can be described in the YAML or JSON serialization of SVD, in a
+//! Microchip (Atmel) ATDF file, in an IP-XACT component or in SystemRDL. The format is picked from
+//! the file extension ("yaml"/"yml", "json", "atdf", "ipxact", "rdl") or given with the
+//! `--source_type` flag. XML files whose root element is an IP-XACT `component` are converted as
+//! such.
+//!
+//! `svd2rust convert -i <input> -o <output>` writes the device as the generator sees it in SVD XML,
+//! YAML or JSON, picked from the output extension or with `--format`. `--patch <file>` first
+//! applies a YAML or JSON merge patch (see [`convert::patch`]) and `--expand` expands arrays,
+//! clusters and derived items.
+//!
+//! `svd2rust lint -i <input>` reports problems of the device, like overlapping fields or missing
+//! reset values, each with a stable code (see [`lint::Code`]). `--format json` prints them as JSON
+//! for CI. The command fails if any of them is an error.
+//!
+//! `svd2rust diff <old> <new>` lists what changed between two revisions of a device, like renamed
+//! registers or moved fields, and the semver impact on the generated crate (see [`diff::Impact`]).
+//! `svd2rust api-diff -i <input> <previous>` generates the code and compares its public items with
+//! a previously generated crate (see [`api::Api`]), which makes svd2rust upgrades reviewable.
+//!
+//! When rendering fails, all errors are reported together with the paths of the failing items
+//! (see [`generate::errors`]). `--fail_fast` stops at the first error instead. Both these errors
+//! and lint diagnostics show the line of the item in XML, YAML or JSON input (see
+//! [`source::SourceMap`]).
+//!
+//! Warnings like renamed union types carry a stable code (see [`generate::warnings::Code`]).
+//! `--deny-warnings` fails if there are any, `--deny W001,W002` only on the given codes.
+//!
+//! With the `parallel` Cargo feature (on by default) peripherals are rendered across threads,
+//! `RAYON_NUM_THREADS` sets their number. The output is the same for any number of threads, and
+//! `--fail_fast` still stops at the first error.
+//!
 //! If using the `--generic_mod` option, the emitted `generic.rs` needs to be moved to `src`, and
 //! [`form`](https://github.com/djmcgill/form) commit fcb397a or newer is required for splitting
 //! the emitted `lib.rs`.
@@ -37,7 +69,7 @@
 //!
 //! - `build.rs`, build script that places `device.x` somewhere the linker can find.
 //! - `device.x`, linker script that weakly aliases all the interrupt handlers to the default
-//! exception handler (`DefaultHandler`).
+//!   exception handler (`DefaultHandler`).
 //! - `lib.rs`, the generated code.
 //!
 //! All these files must be included in the same device crate. The `lib.rs` file contains several
@@ -90,7 +122,7 @@
 //!
 //! - `build.rs`, build script that places `device.x` somewhere the linker can find.
 //! - `device.x`, linker script that weakly aliases all the interrupt handlers to the default
-//! exception handler (`DefaultHandler`).
+//!   exception handler (`DefaultHandler`).
 //! - `lib.rs`, the generated code.
 //!
 //! All these files must be included in the same device crate. The `lib.rs` file contains several
@@ -139,6 +171,55 @@
 //! unstable = ["msp430-atomic"]
 //! ```
 //!
+//! ## target = mips
+//!
+//! When targeting MIPS microcontrollers (e.g. PIC32MX) `svd2rust` generates `build.rs`,
+//! `device.x` and `lib.rs` as well. `device.x` weakly aliases every interrupt handler to
+//! `DefaultHandler` and points the `_vector_N_fn` entries used by the `mips-rt` vector stubs
+//! to the handler of interrupt `N`.
+//!
+//! The resulting crate must provide an opt-in `rt` feature and depend on these crates:
+//!
+//! - [`critical-section`](https://crates.io/crates/critical-section) v1.x
+//! - [`mips-mcu`](https://crates.io/crates/mips-mcu) v0.3.x
+//! - [`mips-rt`](https://crates.io/crates/mips-rt) v0.3.x
+//! - [`vcell`](https://crates.io/crates/vcell) v0.1.x
+//!
+//! ``` toml
+//! [dependencies]
+//! critical-section = { version = "1.0", optional = true }
+//! mips-mcu = "0.3.0"
+//! mips-rt = { version = "0.3.0", optional = true }
+//! vcell = "0.1.0"
+//!
+//! [features]
+//! rt = ["mips-rt"]
+//! ```
+//!
+//! ## target = avr
+//!
+//! When targeting 8-bit AVR microcontrollers `svd2rust` generates `build.rs`, `device.x` and
+//! `lib.rs`. The vector table itself comes from the avr-libc startup code, which jumps to
+//! `__vector_N` for interrupt `N`; the generated `interrupt!` macro exports a handler under that
+//! name and `device.x` falls back to `__vector_default` for the others. 16-bit registers get
+//! `read_ordered`, `write_ordered` and `modify_ordered` methods that access the two bytes in the
+//! order the shared `TEMP` register requires.
+//!
+//! The generated code uses `#![feature(abi_avr_interrupt)]`, so a nightly compiler is required.
+//! The resulting crate must provide an opt-in `rt` feature and depend on these crates:
+//!
+//! - [`critical-section`](https://crates.io/crates/critical-section) v1.x
+//! - [`vcell`](https://crates.io/crates/vcell) v0.1.x
+//!
+//! ``` toml
+//! [dependencies]
+//! critical-section = { version = "1.0", optional = true }
+//! vcell = "0.1.0"
+//!
+//! [features]
+//! rt = []
+//! ```
+//!
 //! ## Other targets
 //!
 //! When the target is riscv or none `svd2rust` will emit only the `lib.rs` file. Like in
@@ -214,6 +295,18 @@
 //! }
 //! ```
 //!
+//! With `--peripheral_arrays`, the instances of a peripheral array, like `DMA%s`, can be gathered
+//! into a `DMA_ARRAY` collection indexed by an enum of their names. The collection takes the
+//! instances, `free` gives them back. Their base addresses are in a const table:
+//!
+//! ```ignore
+//! let p = pac::Peripherals::take().unwrap();
+//! let dma = DMA_ARRAY::new((p.DMA1, p.DMA2));
+//! let cr = dma[DMA_INDEX::DMA2].cr.read().bits(); // or any of `DMA_INDEX::ALL`
+//! let base = DMA_ARRAY::ADDRESSES[DMA_INDEX::DMA1 as usize];
+//! let (dma1, dma2) = dma.free();
+//! ```
+//!
 //! Each peripheral proxy `deref`s to a `RegisterBlock` struct that represents a piece of device
 //! memory. Each field in this `struct` represents one register in the register block associated to
 //! the peripheral.
@@ -249,6 +342,43 @@
 //! }
 //! ```
 //!
+//! Register addresses can be computed at compile time, e.g. for DMA descriptors in `static`s.
+//! Peripherals have an `ADDRESS` const and a `const fn <path>_address` per register, the path
+//! joining the names of the clusters the register is in. They take the element index of each
+//! array on the path, `n`, or `n0`, `n1`, ... from the outermost array. Register blocks have a
+//! `const fn <field>_address(base)` per field, relative to the address of the block. Register
+//! specs have their `OFFSET`:
+//!
+//! ```ignore
+//! static TXDR: usize = I2C1::txdr_address();
+//! static CH2_CR: usize = DMA1::ch_cr_address(2);
+//! assert_eq!(TXDR, i2c1::RegisterBlock::txdr_address(I2C1::ADDRESS));
+//! assert_eq!(i2c1::txdr::TXDR_SPEC::OFFSET, 0x28);
+//! ```
+//!
+//! An address function that would have the name of another method, like the accessor of a
+//! `FOO_ADDRESS` register overlapping `FOO`, is left out with a `W003` warning.
+//!
+//! Register and cluster arrays become Rust arrays, or `ArrayProxy`s with `--const_generic` when
+//! their elements are not contiguous, which can both be iterated over. Arrays that can't be
+//! converted, like those with `dimIndex` names and gaps between the elements, get a separate field
+//! per element and an iterator over them:
+//!
+//! ```ignore
+//! for ch in &dma.ch { .. }
+//! for (i, ch) in dma.ch.enumerate() { .. }
+//! for sr in dma.sr_iter() { .. } // `dma.sra`, `dma.srb`
+//! ```
+//!
+//! With `--index_enums`, arrays with `dimIndex` names are indexed by an enum of the names. They
+//! stay Rust arrays or, when their elements are not contiguous, become `ArrayProxy`s if
+//! `--const_generic` is also passed. The per-name accessors are kept:
+//!
+//! ```ignore
+//! dma.sr[SR_INDEX::A].read(); // same as `dma.sra()`
+//! for i in SR_INDEX::ALL { dma.sr[i].reset(); }
+//! ```
+//!
 //! # `read` / `modify` / `write` API
 //!
 //! Each register in the register block, e.g. the `cr1` field in the `I2C` struct, exposes a
@@ -388,6 +518,24 @@
 //! i2c1.cr2.modify(|r, w| w.stop().bit(!r.stop().bit()));
 //! ```
 //!
+//! ## Field metadata
+//!
+//! Each field has a zero-sized `<FIELD>_FIELD` type in the register module implementing the
+//! `FieldSpec` trait. For field arrays it takes the index of the element, `MODER_FIELD<3>`, and an
+//! index past the end fails to compile. Its `OFFSET`, `WIDTH`, `MASK` and `RESET` consts let generic
+//! code work on any field:
+//!
+//! ```ignore
+//! fn wait_until<F, REG>(reg: &Reg<REG>, value: u32)
+//! where
+//!     F: FieldSpec<REG = REG>,
+//!     REG: Readable + RegisterSpec<Ux = u32>,
+//! {
+//!     while (reg.read().bits() & F::MASK) >> F::OFFSET != value {}
+//! }
+//! wait_until::<i2c1::isr::BUSY_FIELD, _>(&i2c1.isr, 0);
+//! ```
+//!
 //! # enumeratedValues
 //!
 //! If your SVD uses the `<enumeratedValues>` feature, then the API will be *extended* to provide
@@ -456,6 +604,46 @@
 //! gpioa.dir.write(|w| w.pin0().bit(true));
 //! ```
 //!
+//! The fields of a field array, like `ODR%s`, have an accessor each. `odr_iter` iterates over their
+//! readers and, when the indexes are a range, `odr_checked(n)` reads and `odr_checked(n, variant)`
+//! writes the field `n`, returning `None` if there is no such field:
+//!
+//! ```ignore
+//! let high = gpioa.odr.read().odr_iter().filter(|pin| pin.is_high()).count();
+//! gpioa.odr.write(|w| w.odr_checked(5, gpioa::odr::ODR_A::High).unwrap());
+//! ```
+//!
+//! A field with `<useEnumeratedValues>true</useEnumeratedValues>` as `<writeConstraint>` can only
+//! be written with `variant` and the methods named after the variants, its writer has no `bits`.
+//!
+//! When the SVD limits the values of a field with a `<writeConstraint>` range, its writer gets a
+//! safe `set` method failing with `OutOfRange` outside of `MIN..=MAX`. It only has `variant` if
+//! the field has enumerated values:
+//!
+//! ```ignore
+//! // <range><minimum>1</minimum><maximum>1000</maximum></range>
+//! tim1.psc.write(|w| w.psc().set(10).unwrap());
+//! ```
+//!
+//! A `<value>` with don't-care bits, like `#1xx`, gives a variant that is read for any of the values
+//! matching it, the reader compares the other bits: `bits & 0b100 == 0b100`. Writing the variant
+//! writes the don't-care bits as `0`. In JSON and YAML inputs the pattern is given as a string,
+//! `"#1xx"`.
+//!
+//! An `<enumeratedValue>` with `<isDefault>true</isDefault>` stands for all the values not listed.
+//! It becomes a variant carrying the raw bits, so `variant` always returns one of the variants and
+//! the field can be written safely. `variant` never returns it for a listed value, so construct
+//! the listed variants directly: `Center(0)` is not equal to `EdgeAligned` even though both write
+//! `0`. The bits past the field width are dropped on write:
+//!
+//! ```ignore
+//! // enum CMS_A { EdgeAligned, Center(u8) }
+//! match tim1.cr1.read().cms().variant() {
+//!     tim1::cr1::CMS_A::EdgeAligned => { .. },
+//!     tim1::cr1::CMS_A::Center(bits) => { .. },
+//! }
+//! ```
+//!
 //! # Interrupt API
 //!
 //! SVD files also describe the device interrupts. svd2rust generated crates expose an enumeration
@@ -508,7 +696,13 @@
 use quote::quote;
 use svd_parser::svd;
 
+pub mod api;
+pub mod convert;
+pub mod diff;
 pub mod generate;
+pub mod input;
+pub mod lint;
+pub mod source;
 pub mod util;
 
 pub use crate::util::{Config, Target};
@@ -517,6 +711,8 @@ pub use crate::util::{Config, Target};
 pub struct Generation {
     pub lib_rs: String,
     pub device_specific: Option<DeviceSpecific>,
+    /// Warnings about the rendered items, see [`generate::warnings`]
+    pub warnings: Vec<generate::warnings::Warning>,
 }
 
 #[non_exhaustive]
@@ -536,13 +732,33 @@ pub enum SvdError {
 }
 
 /// Generates rust code for the specified svd content.
+///
+/// When items fail to render, the error is a [`RenderErrors`](generate::errors::RenderErrors)
+/// holding the error, path and location of each of them. When warnings are denied, it is a
+/// [`DeniedWarnings`](generate::warnings::DeniedWarnings) with all the located warnings.
 pub fn generate(input: &str, config: &Config) -> Result<Generation> {
     use std::fmt::Write;
 
     let device = load_from(input, config)?;
+    let file = config
+        .input
+        .as_ref()
+        .map_or("<input>".into(), |file| file.display().to_string());
+    let sources = source::SourceMap::new(&file, input, config.source_type);
+    let dont_care = input::dont_care::Patterns::new(input, config.source_type);
     let mut device_x = String::new();
-    let items =
-        generate::device::render(&device, config, &mut device_x).or(Err(SvdError::Render))?;
+    let mut warnings = generate::warnings::Warnings::default();
+    // Errors of the items that failed are returned as `RenderErrors`, with their locations
+    let items = generate::device::render(&device, &dont_care, config, &mut device_x, &mut warnings)
+        .map_err(|e| match e.downcast::<generate::errors::RenderErrors>() {
+            Ok(mut errors) => {
+                errors.locate(&sources);
+                errors.into()
+            }
+            Err(e) => e.context(SvdError::Render),
+        })?;
+    warnings.locate(&sources);
+    warnings.deny(config)?;
 
     let mut lib_rs = String::new();
     writeln!(
@@ -566,32 +782,67 @@ pub fn generate(input: &str, config: &Config) -> Result<Generation> {
     Ok(Generation {
         lib_rs,
         device_specific,
+        warnings: warnings.0,
     })
 }
 
 /// Load a [Device] from a string slice with given [config](crate::util::Config).
 pub fn load_from(input: &str, config: &crate::util::Config) -> Result<svd::Device> {
-    use self::util::SourceType;
     use svd_parser::ValidateLevel;
 
+    let validate_level = if config.strict {
+        ValidateLevel::Strict
+    } else {
+        ValidateLevel::Weak
+    };
+    load_from_with_level(input, config, validate_level)
+}
+
+/// Load a [Device](svd::Device) like [`load_from`], validating it at the given level instead of
+/// the one chosen by `config.strict`.
+pub fn load_from_with_level(
+    input: &str,
+    config: &crate::util::Config,
+    validate_level: svd_parser::ValidateLevel,
+) -> Result<svd::Device> {
+    use self::util::SourceType;
+
     let mut device = match config.source_type {
+        #[cfg(feature = "ipxact")]
+        SourceType::Xml if input::ipxact::is_component(input) => {
+            input::ipxact::parse(input, validate_level)
+                .with_context(|| "Error converting IP-XACT file".to_string())?
+        }
         SourceType::Xml => {
             let mut parser_config = svd_parser::Config::default();
-            parser_config.validate_level = if config.strict {
-                ValidateLevel::Strict
-            } else {
-                ValidateLevel::Weak
-            };
+            parser_config.validate_level = validate_level;
 
             svd_parser::parse_with_config(input, &parser_config)
                 .with_context(|| "Error parsing SVD XML file".to_string())?
         }
         #[cfg(feature = "yaml")]
         SourceType::Yaml => serde_yaml::from_str(input)
+            .and_then(|mut yaml| {
+                input::dont_care::take_yaml(&mut yaml);
+                serde_yaml::from_value(yaml)
+            })
             .with_context(|| "Error parsing SVD YAML file".to_string())?,
         #[cfg(feature = "json")]
         SourceType::Json => serde_json::from_str(input)
+            .and_then(|mut json| {
+                input::dont_care::take_json(&mut json);
+                serde_json::from_value(json)
+            })
             .with_context(|| "Error parsing SVD JSON file".to_string())?,
+        #[cfg(feature = "atdf")]
+        SourceType::Atdf => input::atdf::parse(input, validate_level)
+            .with_context(|| "Error converting ATDF file".to_string())?,
+        #[cfg(feature = "ipxact")]
+        SourceType::IpXact => input::ipxact::parse(input, validate_level)
+            .with_context(|| "Error converting IP-XACT file".to_string())?,
+        #[cfg(feature = "systemrdl")]
+        SourceType::SystemRdl => input::systemrdl::parse(input, validate_level)
+            .with_context(|| "Error converting SystemRDL file".to_string())?,
     };
     svd_parser::expand_properties(&mut device);
     Ok(device)
@@ -0,0 +1,545 @@
+//! Checks for device descriptions the generator tolerates or rejects
+//!
+//! [`lint`] looks at the device the way the generator does, with arrays and derived items
+//! expanded, and reports each problem as a [`Diagnostic`] carrying a stable [`Code`].
+
+use crate::source::Location;
+use crate::svd::{Device, Field, Register, RegisterCluster};
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+/// How bad a [`Diagnostic`] is
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The generated code works but is likely not what the SVD author meant
+    Warning,
+    /// The generated code is wrong or the generator fails
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// Kind of a [`Diagnostic`], its code never changes once released
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Code {
+    /// Fields of a register share bits
+    OverlappingFields,
+    /// Registers share addresses and are rendered as a union
+    UnionRegisters,
+    /// Register has no reset value
+    MissingResetValue,
+    /// Enumerated value doesn't fit in its field
+    EnumValueTooWide,
+    /// Different interrupts have the same number
+    DuplicateInterrupt,
+    /// Peripheral has no address block or registers outside of them
+    OutsideAddressBlock,
+}
+
+impl Code {
+    /// Stable identifier of the code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::OverlappingFields => "L001",
+            Self::UnionRegisters => "L002",
+            Self::MissingResetValue => "L003",
+            Self::EnumValueTooWide => "L004",
+            Self::DuplicateInterrupt => "L005",
+            Self::OutsideAddressBlock => "L006",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::OverlappingFields | Self::EnumValueTooWide | Self::DuplicateInterrupt => {
+                Severity::Error
+            }
+            Self::UnionRegisters | Self::MissingResetValue | Self::OutsideAddressBlock => {
+                Severity::Warning
+            }
+        }
+    }
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Code {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// A problem found in the device
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub code: Code,
+    pub severity: Severity,
+    /// Dot separated path of the offending item, like `TIM1.CR1.CEN`
+    pub path: String,
+    pub message: String,
+    /// Where the item is in the input, filled in with [`SourceMap`](crate::source::SourceMap)
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub location: Option<Location>,
+}
+
+impl Diagnostic {
+    fn new(code: Code, path: String, message: String) -> Self {
+        Self {
+            code,
+            severity: code.severity(),
+            path,
+            message,
+            location: None,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}] {}: {}",
+            self.severity, self.code, self.path, self.message
+        )
+    }
+}
+
+/// Checks `device` and returns the problems found, ordered by path
+pub fn lint(device: &Device) -> Result<Vec<Diagnostic>> {
+    let expanded = svd_parser::expand(device).context("Error expanding device")?;
+    // Derived peripherals repeat the problems of the one they derive from
+    let derived = device
+        .peripherals
+        .iter()
+        .filter(|p| p.derived_from.is_some())
+        .map(|p| p.name.as_str())
+        .collect::<HashSet<_>>();
+
+    let mut diags = Vec::new();
+    let mut interrupts = BTreeMap::<u32, Vec<&str>>::new();
+    for p in &expanded.peripherals {
+        for i in &p.interrupt {
+            let names = interrupts.entry(i.value).or_default();
+            if !names.contains(&i.name.as_str()) {
+                names.push(&i.name);
+            }
+        }
+        if derived.contains(p.name.as_str()) {
+            continue;
+        }
+
+        let registers = p.registers.as_deref().unwrap_or_default();
+        match p.address_block.as_deref() {
+            None | Some([]) => diags.push(Diagnostic::new(
+                Code::OutsideAddressBlock,
+                p.name.clone(),
+                "peripheral has no address block".to_string(),
+            )),
+            Some(blocks) => {
+                for (path, start, end) in extents(registers, &p.name, 0) {
+                    if !blocks.iter().any(|b| {
+                        u64::from(b.offset) <= start
+                            && end <= u64::from(b.offset) + u64::from(b.size)
+                    }) {
+                        diags.push(Diagnostic::new(
+                            Code::OutsideAddressBlock,
+                            path,
+                            format!(
+                                "bytes {:#x}..{:#x} are outside of the address blocks",
+                                start, end
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+        block(registers, &p.name, &mut diags);
+    }
+
+    for (value, names) in interrupts {
+        if names.len() > 1 {
+            diags.push(Diagnostic::new(
+                Code::DuplicateInterrupt,
+                names.join(", "),
+                format!("interrupts share number {}", value),
+            ));
+        }
+    }
+
+    diags.sort_by(|a, b| (&a.path, a.code).cmp(&(&b.path, b.code)));
+    Ok(diags)
+}
+
+/// Byte ranges, relative to the peripheral, of all registers of a block
+fn extents(items: &[RegisterCluster], path: &str, base: u64) -> Vec<(String, u64, u64)> {
+    let mut out = Vec::new();
+    for item in items {
+        match item {
+            RegisterCluster::Register(r) => {
+                let start = base + u64::from(r.address_offset);
+                let size = u64::from(r.properties.size.unwrap_or(32) / 8);
+                out.push((format!("{}.{}", path, r.name), start, start + size));
+            }
+            RegisterCluster::Cluster(c) => out.extend(extents(
+                &c.children,
+                &format!("{}.{}", path, c.name),
+                base + u64::from(c.address_offset),
+            )),
+        }
+    }
+    out
+}
+
+/// Checks the registers and clusters of a peripheral or cluster
+fn block(items: &[RegisterCluster], path: &str, diags: &mut Vec<Diagnostic>) {
+    // Overlapping registers of the same block are rendered as unions
+    let mut ranges = items
+        .iter()
+        .map(|item| match item {
+            RegisterCluster::Register(r) => {
+                let start = u64::from(r.address_offset);
+                let size = u64::from(r.properties.size.unwrap_or(32) / 8);
+                (r.name.as_str(), start, start + size)
+            }
+            RegisterCluster::Cluster(c) => {
+                let start = u64::from(c.address_offset);
+                let end = extents(&c.children, "", start)
+                    .iter()
+                    .map(|(_, _, end)| *end)
+                    .max()
+                    .unwrap_or(start);
+                (c.name.as_str(), start, end)
+            }
+        })
+        .collect::<Vec<_>>();
+    ranges.sort_by_key(|(_, start, _)| *start);
+    for (i, (name, start, end)) in ranges.iter().enumerate() {
+        for (other, other_start, _) in &ranges[i + 1..] {
+            if other_start >= end {
+                break;
+            }
+            diags.push(Diagnostic::new(
+                Code::UnionRegisters,
+                format!("{}.{}", path, name),
+                format!(
+                    "overlaps `{}` at {:#x}, both are rendered in a union",
+                    other,
+                    start.max(other_start)
+                ),
+            ));
+        }
+    }
+
+    for item in items {
+        match item {
+            RegisterCluster::Register(r) => register(r, &format!("{}.{}", path, r.name), diags),
+            RegisterCluster::Cluster(c) => {
+                block(&c.children, &format!("{}.{}", path, c.name), diags)
+            }
+        }
+    }
+}
+
+fn register(r: &Register, path: &str, diags: &mut Vec<Diagnostic>) {
+    if r.properties.reset_value.is_none() {
+        diags.push(Diagnostic::new(
+            Code::MissingResetValue,
+            path.to_string(),
+            "register has no reset value".to_string(),
+        ));
+    }
+
+    let fields = r.fields.as_deref().unwrap_or_default();
+    for (i, f) in fields.iter().enumerate() {
+        for other in &fields[i + 1..] {
+            // Read-only and write-only fields can share bits
+            let split = matches!(
+                (f.access, other.access),
+                (Some(a), Some(b)) if (a.can_read() != b.can_read()) && (a.can_write() != b.can_write())
+            );
+            if f.bitmask() & other.bitmask() != 0 && !split {
+                diags.push(Diagnostic::new(
+                    Code::OverlappingFields,
+                    format!("{}.{}", path, f.name),
+                    format!(
+                        "shares bits {:#x} with `{}`",
+                        f.bitmask() & other.bitmask(),
+                        other.name
+                    ),
+                ));
+            }
+        }
+        field(f, &format!("{}.{}", path, f.name), diags);
+    }
+}
+
+fn field(f: &Field, path: &str, diags: &mut Vec<Diagnostic>) {
+    let width = f.bit_width();
+    for evs in &f.enumerated_values {
+        for ev in &evs.values {
+            if let Some(value) = ev.value {
+                if width < 64 && value >> width != 0 {
+                    diags.push(Diagnostic::new(
+                        Code::EnumValueTooWide,
+                        format!("{}.{}", path, ev.name),
+                        format!("value {} doesn't fit in {} bits", value, width),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svd::ValidateLevel;
+
+    /// Device with a peripheral `P`, its address block and `registers`, then `more` peripherals
+    fn device(registers: &str, more: &str) -> Device {
+        let xml = format!(
+            r#"<device>
+  <name>DEV</name>
+  <addressUnitBits>8</addressUnitBits>
+  <width>32</width>
+  <size>32</size>
+  <access>read-write</access>
+  <resetValue>0</resetValue>
+  <peripherals>
+    <peripheral>
+      <name>P</name>
+      <baseAddress>0x1000</baseAddress>
+      <addressBlock><offset>0</offset><size>0x10</size><usage>registers</usage></addressBlock>
+      <registers>{registers}</registers>
+    </peripheral>
+    {more}
+  </peripherals>
+</device>"#
+        );
+        // Like `svd2rust lint`, which lets the linter report what validation would reject
+        crate::load_from_with_level(&xml, &Default::default(), ValidateLevel::Disabled).unwrap()
+    }
+
+    fn register(name: &str, offset: u32, fields: &str) -> String {
+        format!(
+            "<register><name>{name}</name><addressOffset>{offset}</addressOffset>\
+             <fields>{fields}</fields></register>"
+        )
+    }
+
+    fn field(name: &str, range: &str, access: &str) -> String {
+        format!("<field><name>{name}</name><bitRange>{range}</bitRange>{access}</field>")
+    }
+
+    fn codes(device: &Device) -> Vec<(Code, String)> {
+        lint(device)
+            .unwrap()
+            .into_iter()
+            .map(|d| (d.code, d.path))
+            .collect()
+    }
+
+    #[test]
+    fn clean() {
+        let regs = format!(
+            "{}{}",
+            register("CR", 0, &field("EN", "[0:0]", "")),
+            register("SR", 4, "")
+        );
+        assert!(lint(&device(&regs, "")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn overlapping_fields() {
+        let fields = [
+            field("A", "[3:0]", ""),
+            field("B", "[4:3]", ""),
+            field("C", "[7:5]", ""),
+        ]
+        .concat();
+        let diags = lint(&device(&register("CR", 0, &fields), "")).unwrap();
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, Code::OverlappingFields);
+        assert_eq!(diags[0].severity, Severity::Error);
+        assert_eq!(diags[0].path, "P.CR.A");
+        assert_eq!(diags[0].message, "shares bits 0x8 with `B`");
+    }
+
+    #[test]
+    fn read_write_split() {
+        let access = |a| format!("<access>{a}</access>");
+        // A read-only and a write-only field may share bits
+        let fields = [
+            field("RX", "[7:0]", &access("read-only")),
+            field("TX", "[7:0]", &access("write-only")),
+        ]
+        .concat();
+        assert!(codes(&device(&register("DR", 0, &fields), "")).is_empty());
+        // Not when one of them can be both read and written
+        let fields = [
+            field("RX", "[7:0]", &access("read-only")),
+            field("TX", "[7:0]", &access("read-write")),
+        ]
+        .concat();
+        assert_eq!(
+            codes(&device(&register("DR", 0, &fields), "")),
+            [(Code::OverlappingFields, "P.DR.RX".to_string())]
+        );
+        // Nor when the access of one of them is unknown
+        let fields = [
+            field("RX", "[7:0]", &access("read-only")),
+            field("TX", "[7:0]", ""),
+        ]
+        .concat();
+        assert_eq!(codes(&device(&register("DR", 0, &fields), "")).len(), 1);
+    }
+
+    #[test]
+    fn unions() {
+        let regs = [
+            register("A", 0, ""),
+            register("B", 2, ""),
+            register("C", 8, ""),
+            "<cluster><name>CL</name><addressOffset>0xc</addressOffset>".to_string(),
+            register("D", 0, ""),
+            register("E", 4, ""),
+            "</cluster>".to_string(),
+            register("F", 0x10, ""),
+        ]
+        .concat();
+        let diags = lint(&device(&regs, "")).unwrap();
+        let unions = diags
+            .iter()
+            .filter(|d| d.code == Code::UnionRegisters)
+            .map(|d| (d.path.as_str(), d.message.as_str()))
+            .collect::<Vec<_>>();
+        // Adjacent registers, like `C` and `CL_D`, don't overlap. Expanding the device moves the
+        // registers of clusters into their parent.
+        assert_eq!(
+            unions,
+            [
+                ("P.A", "overlaps `B` at 0x2, both are rendered in a union"),
+                (
+                    "P.CL_E",
+                    "overlaps `F` at 0x10, both are rendered in a union"
+                ),
+            ]
+        );
+        assert_eq!(diags[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn reset_values_and_enum_values() {
+        let fields = "<field><name>MODE</name><bitRange>[1:0]</bitRange><enumeratedValues>\
+            <enumeratedValue><name>ON</name><value>3</value></enumeratedValue>\
+            <enumeratedValue><name>BIG</name><value>4</value></enumeratedValue>\
+            </enumeratedValues></field>";
+        let regs = format!(
+            "<register><name>CR</name><addressOffset>0</addressOffset>\
+             <resetValue>0</resetValue><fields>{fields}</fields></register>"
+        );
+        let mut device = device(&regs, "");
+        assert_eq!(
+            codes(&device),
+            [(Code::EnumValueTooWide, "P.CR.MODE.BIG".to_string())]
+        );
+        device.default_register_properties.reset_value = None;
+        device.peripherals[0].registers.as_mut().unwrap()[0] = RegisterCluster::Register(
+            crate::svd::RegisterInfo::builder()
+                .name("SR".to_string())
+                .address_offset(4)
+                .build(ValidateLevel::Disabled)
+                .unwrap()
+                .single(),
+        );
+        assert_eq!(
+            codes(&device),
+            [(Code::MissingResetValue, "P.SR".to_string())]
+        );
+    }
+
+    #[test]
+    fn interrupts_and_derived() {
+        let more = r#"<peripheral derivedFrom="P">
+      <name>P2</name>
+      <baseAddress>0x2000</baseAddress>
+      <interrupt><name>P2</name><value>1</value></interrupt>
+    </peripheral>
+    <peripheral derivedFrom="P">
+      <name>P3</name>
+      <baseAddress>0x3000</baseAddress>
+      <interrupt><name>P2</name><value>1</value></interrupt>
+      <interrupt><name>P3</name><value>2</value></interrupt>
+    </peripheral>
+    <peripheral derivedFrom="P">
+      <name>P4</name>
+      <baseAddress>0x4000</baseAddress>
+      <interrupt><name>OTHER</name><value>2</value></interrupt>
+    </peripheral>"#;
+        // The union of `P` is not repeated for the peripherals derived from it. Interrupts
+        // sharing a name are a shared line, not duplicates.
+        let regs = [register("A", 0, ""), register("B", 0, "")].concat();
+        assert_eq!(
+            codes(&device(&regs, more)),
+            [
+                (Code::UnionRegisters, "P.A".to_string()),
+                (Code::DuplicateInterrupt, "P3, OTHER".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn address_blocks() {
+        let more = r#"<peripheral>
+      <name>Q</name>
+      <baseAddress>0x2000</baseAddress>
+      <registers>
+        <register><name>DATA</name><addressOffset>0</addressOffset></register>
+      </registers>
+    </peripheral>"#;
+        // `WIDE` ends past the 16 bytes of the block
+        let regs = format!(
+            "{}<register><name>WIDE</name><addressOffset>0xc</addressOffset>\
+             <size>64</size></register>",
+            register("CR", 0, "")
+        );
+        let diags = lint(&device(&regs, more)).unwrap();
+        assert_eq!(
+            diags
+                .iter()
+                .map(|d| (d.code, d.path.as_str(), d.message.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    Code::OutsideAddressBlock,
+                    "P.WIDE",
+                    "bytes 0xc..0x14 are outside of the address blocks"
+                ),
+                (
+                    Code::OutsideAddressBlock,
+                    "Q",
+                    "peripheral has no address block"
+                ),
+            ]
+        );
+    }
+}
@@ -1,16 +1,21 @@
 #![recursion_limit = "128"]
 
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 
 use std::fs::File;
 use std::io::Write;
+use std::path::Path;
 use std::process;
 
-use anyhow::{Context, Result};
-use clap::{Arg, ArgAction, Command};
+use anyhow::{anyhow, Context, Result};
+use clap::{Arg, ArgAction, ArgMatches, Command};
 
 use svd2rust::{
-    generate, load_from,
+    convert,
+    generate::{self, errors::RenderErrors, warnings::Warnings},
+    input::dont_care::Patterns,
+    load_from, load_from_with_level,
+    source::SourceMap,
     util::{self, build_rs, Config, SourceType, Target},
 };
 
@@ -118,6 +123,37 @@ fn run() -> Result<()> {
                 .action(ArgAction::SetTrue)
                 .help("Use array increment for cluster size"),
         )
+        .arg(
+            Arg::new("index_enums")
+                .long("index_enums")
+                .action(ArgAction::SetTrue)
+                .help("Keep arrays with named dimIndex as arrays indexed by an enum of the names"),
+        )
+        .arg(
+            Arg::new("peripheral_arrays")
+                .long("peripheral_arrays")
+                .action(ArgAction::SetTrue)
+                .help("Generate collections, indexed by an enum, taking the instances of peripheral arrays"),
+        )
+        .arg(
+            Arg::new("fail_fast")
+                .long("fail_fast")
+                .action(ArgAction::SetTrue)
+                .help("Stop at the first rendering error instead of reporting all of them"),
+        )
+        .arg(
+            Arg::new("deny_warnings")
+                .long("deny-warnings")
+                .action(ArgAction::SetTrue)
+                .help("Fail if there are warnings"),
+        )
+        .arg(
+            Arg::new("deny")
+                .long("deny")
+                .action(ArgAction::Set)
+                .value_name("CODES")
+                .help("Fail on warnings with the given comma separated codes, like W001,W002"),
+        )
         .arg(
             Arg::new("make_mod")
                 .long("make_mod")
@@ -160,11 +196,122 @@ fn run() -> Result<()> {
                 .action(ArgAction::Set)
                 .value_parser(["off", "error", "warn", "info", "debug", "trace"]),
         )
+        .subcommand(
+            Command::new("convert")
+                .about("Write the loaded device as SVD XML, YAML or JSON")
+                .arg(
+                    Arg::new("input")
+                        .help("Input file")
+                        .short('i')
+                        .action(ArgAction::Set)
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .help("Output file, the standard output if omitted")
+                        .action(ArgAction::Set)
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Output format, taken from the output file extension by default")
+                        .action(ArgAction::Set)
+                        .value_parser(["xml", "yaml", "json"]),
+                )
+                .arg(
+                    Arg::new("patch")
+                        .long("patch")
+                        .help("YAML or JSON merge patch applied to the device")
+                        .action(ArgAction::Set)
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("expand")
+                        .long("expand")
+                        .action(ArgAction::SetTrue)
+                        .help("Expand arrays, clusters and derived items"),
+                ),
+        )
+        .subcommand(
+            Command::new("api-diff")
+                .about("Compare the public API of the generated code with a previously generated tree, failing on breaking changes")
+                .arg(
+                    Arg::new("input")
+                        .help("Input file")
+                        .short('i')
+                        .action(ArgAction::Set)
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("previous")
+                        .help("Previously generated crate directory or lib.rs")
+                        .required(true)
+                        .action(ArgAction::Set)
+                        .value_name("PREVIOUS"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Report format")
+                        .action(ArgAction::Set)
+                        .value_parser(["human", "json"])
+                        .default_value("human"),
+                ),
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("Report the changes between two revisions of a device and their semver impact")
+                .arg(
+                    Arg::new("old")
+                        .help("Old input file")
+                        .required(true)
+                        .action(ArgAction::Set)
+                        .value_name("OLD"),
+                )
+                .arg(
+                    Arg::new("new")
+                        .help("New input file")
+                        .required(true)
+                        .action(ArgAction::Set)
+                        .value_name("NEW"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Report format")
+                        .action(ArgAction::Set)
+                        .value_parser(["human", "json"])
+                        .default_value("human"),
+                ),
+        )
+        .subcommand(
+            Command::new("lint")
+                .about("Report problems of the device, fails if any is an error")
+                .arg(
+                    Arg::new("input")
+                        .help("Input file")
+                        .short('i')
+                        .action(ArgAction::Set)
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Report format")
+                        .action(ArgAction::Set)
+                        .value_parser(["human", "json"])
+                        .default_value("human"),
+                ),
+        )
         .version(concat!(
             env!("CARGO_PKG_VERSION"),
             include_str!(concat!(env!("OUT_DIR"), "/commit-info.txt"))
         ));
 
+    let matches = app.clone().get_matches();
     let mut config = match parse_configs(app) {
         Ok(config) => {
             setup_logging(&config.log_level);
@@ -178,6 +325,10 @@ fn run() -> Result<()> {
 
     debug!("Current svd2rust config: {config:#?}");
 
+    if let Some(matches) = matches.subcommand_matches("diff") {
+        return diff(&config, matches);
+    }
+
     let input = &mut String::new();
     match config.input.as_ref() {
         Some(file) => {
@@ -199,14 +350,55 @@ fn run() -> Result<()> {
         config.source_type = SourceType::from_path(file)
     }
     let path = &config.output_dir;
+    let sources = SourceMap::new(
+        &config
+            .input
+            .as_ref()
+            .map_or("<stdin>".into(), |file| file.display().to_string()),
+        input,
+        config.source_type,
+    );
 
     info!("Parsing device from SVD file");
+    if let Some(matches) = matches.subcommand_matches("lint") {
+        // Let the linter report what validation would reject
+        let device = load_from_with_level(input, &config, svd_parser::ValidateLevel::Disabled)?;
+        return lint(&device, &sources, matches);
+    }
     let device = load_from(input, &config)?;
 
+    if let Some(matches) = matches.subcommand_matches("convert") {
+        return convert(device, matches);
+    }
+
+    if matches.subcommand_matches("api-diff").is_some() {
+        // Compare `generic` inline instead of writing it out
+        config.generic_mod = false;
+    }
+    let dont_care = Patterns::new(input, config.source_type);
     let mut device_x = String::new();
     info!("Rendering device");
-    let items = generate::device::render(&device, &config, &mut device_x)
-        .with_context(|| "Error rendering device")?;
+    let mut warnings = Warnings::default();
+    let items =
+        generate::device::render(&device, &dont_care, &config, &mut device_x, &mut warnings)
+            .map_err(|e| match e.downcast::<RenderErrors>() {
+                Ok(mut errors) => {
+                    errors.locate(&sources);
+                    errors.into()
+                }
+                Err(e) => e,
+            })
+            .with_context(|| "Error rendering device");
+    warnings.locate(&sources);
+    for warning in &warnings.0 {
+        warn!("{}", warning);
+    }
+    let items = items?;
+    warnings.deny(&config)?;
+
+    if let Some(matches) = matches.subcommand_matches("api-diff") {
+        return api_diff(items, matches);
+    }
 
     let filename = if config.make_mod { "mod.rs" } else { "lib.rs" };
     let mut file = File::create(path.join(filename)).expect("Couldn't create output file");
@@ -220,6 +412,8 @@ fn run() -> Result<()> {
         Target::Msp430,
         Target::XtensaLX,
         Target::RISCV,
+        Target::Mips,
+        Target::Avr,
     ]
     .contains(&config.target)
     {
@@ -269,6 +463,132 @@ fn run() -> Result<()> {
     Ok(())
 }
 
+fn convert(mut device: svd_parser::svd::Device, matches: &ArgMatches) -> Result<()> {
+    let output = matches.get_one::<String>("output").map(Path::new);
+    let format = match (matches.get_one::<String>("format"), output) {
+        (Some(format), _) => SourceType::from_extension(format),
+        (None, Some(output)) => output
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(SourceType::from_extension),
+        (None, None) => Some(SourceType::Xml),
+    }
+    .ok_or_else(|| anyhow!("Cannot deduce the output format, use --format"))?;
+
+    if let Some(patch) = matches.get_one::<String>("patch") {
+        #[cfg(feature = "json")]
+        {
+            let patch = Path::new(patch);
+            let text = std::fs::read_to_string(patch).context("Cannot read the patch file")?;
+            convert::patch(&mut device, &text, SourceType::from_path(patch))
+                .with_context(|| format!("Error applying patch {}", patch.display()))?;
+        }
+        #[cfg(not(feature = "json"))]
+        anyhow::bail!("Applying patch {} requires the `json` feature", patch);
+    }
+    if matches.get_flag("expand") {
+        info!("Expanding device");
+        device = svd_parser::expand(&device).context("Error expanding device")?;
+    }
+
+    let data = convert::encode_to(&device, format)?;
+    match output {
+        Some(output) => std::fs::write(output, data).context("Cannot write the output file")?,
+        None => std::io::stdout()
+            .write_all(data.as_bytes())
+            .context("Cannot write to stdout")?,
+    }
+    Ok(())
+}
+
+fn api_diff(items: proc_macro2::TokenStream, matches: &ArgMatches) -> Result<()> {
+    use svd2rust::api::{self, Api};
+
+    let previous = Path::new(matches.get_one::<String>("previous").unwrap());
+    let changes = api::diff(&Api::from_path(previous)?, &Api::from_tokens(items)?);
+    let breaking = changes.iter().filter(|c| c.is_breaking()).count();
+
+    if matches.get_one::<String>("format").map(String::as_str) == Some("json") {
+        #[cfg(feature = "json")]
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+        #[cfg(not(feature = "json"))]
+        anyhow::bail!("JSON reports require the `json` feature");
+    } else {
+        for change in changes.iter().filter(|c| c.is_breaking()) {
+            println!("{}", change);
+        }
+        println!(
+            "{} breaking changes, {} additions",
+            breaking,
+            changes.len() - breaking
+        );
+    }
+    if breaking > 0 {
+        anyhow::bail!("{} breaking changes", breaking);
+    }
+    Ok(())
+}
+
+fn diff(config: &Config, matches: &ArgMatches) -> Result<()> {
+    let load = |arg: &str| {
+        let file = Path::new(matches.get_one::<String>(arg).unwrap());
+        let input = std::fs::read_to_string(file)
+            .with_context(|| format!("Cannot read {}", file.display()))?;
+        let mut config = config.clone();
+        config.source_type = SourceType::from_path(file);
+        load_from(&input, &config).with_context(|| format!("Error loading {}", file.display()))
+    };
+    let changes = svd2rust::diff::diff(&load("old")?, &load("new")?)?;
+
+    if matches.get_one::<String>("format").map(String::as_str) == Some("json") {
+        #[cfg(feature = "json")]
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+        #[cfg(not(feature = "json"))]
+        anyhow::bail!("JSON reports require the `json` feature");
+    } else {
+        for change in &changes {
+            println!("{}", change);
+        }
+        match svd2rust::diff::impact(&changes) {
+            Some(impact) => println!("{} changes, semver impact: {}", changes.len(), impact),
+            None => println!("No changes"),
+        }
+    }
+    Ok(())
+}
+
+fn lint(device: &svd_parser::svd::Device, sources: &SourceMap, matches: &ArgMatches) -> Result<()> {
+    use svd2rust::lint::Severity;
+
+    let mut diags = svd2rust::lint::lint(device)?;
+    for diag in &mut diags {
+        diag.location = sources.locate(&diag.path);
+    }
+    let errors = diags
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    if matches.get_one::<String>("format").map(String::as_str) == Some("json") {
+        #[cfg(feature = "json")]
+        println!("{}", serde_json::to_string_pretty(&diags)?);
+        #[cfg(not(feature = "json"))]
+        anyhow::bail!("JSON reports require the `json` feature");
+    } else {
+        for diag in &diags {
+            println!("{}", diag);
+            if let Some(location) = &diag.location {
+                println!("{}", location.snippet());
+            }
+        }
+        println!("{} errors, {} warnings", errors, diags.len() - errors);
+    }
+
+    if errors > 0 {
+        anyhow::bail!("Found {} errors", errors);
+    }
+    Ok(())
+}
+
 fn setup_logging(log_level: &Option<String>) {
     // * Log at info by default.
     // * Allow users the option of setting complex logging filters using
@@ -0,0 +1,316 @@
+//! Locating device items in the input file
+//!
+//! Diagnostics name items with dot separated paths like `TIM1.CR1.CEN`. [`SourceMap`] finds the
+//! `name` of each path segment in the XML, YAML or JSON text the device was loaded from, so the
+//! diagnostic can point at the file, line and column of the item and show the line.
+//!
+//! Each segment is looked for among the descendants of the previous one, the shallowest first.
+//! Items are not told apart by anything else than their name: of two siblings with the same name,
+//! like a register and its `derivedFrom` copy in another cluster of the same name, the first one
+//! is pointed at.
+
+use crate::util::SourceType;
+use std::fmt;
+
+/// Position of an item in the input file
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+    pub file: String,
+    /// 1-based line
+    pub line: usize,
+    /// 1-based column, in characters
+    pub column: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    source_line: String,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    len: usize,
+}
+
+impl Location {
+    /// The source line of the item with its name underlined
+    pub fn snippet(&self) -> String {
+        let number = self.line.to_string();
+        let pad = " ".repeat(number.len());
+        format!(
+            "{pad}--> {self}\n{pad} |\n{number} | {}\n{pad} | {}{}",
+            self.source_line,
+            " ".repeat(self.column - 1),
+            "^".repeat(self.len.max(1)),
+        )
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// A `name` found in the input
+struct Name {
+    value: String,
+    /// Byte offset of the value
+    offset: usize,
+    /// Nesting depth of the item the name belongs to
+    depth: usize,
+}
+
+/// Names of the items of an input file, in order of appearance
+pub struct SourceMap<'a> {
+    file: String,
+    text: &'a str,
+    names: Vec<Name>,
+}
+
+impl<'a> SourceMap<'a> {
+    /// Collects the names of `text`, read from `file` in the given format. Formats without named
+    /// elements, like SystemRDL, can't be mapped and locate nothing.
+    pub fn new(file: &str, text: &'a str, format: SourceType) -> Self {
+        let names = match format {
+            SourceType::Xml => xml_names(text),
+            #[cfg(feature = "atdf")]
+            SourceType::Atdf => xml_names(text),
+            #[cfg(feature = "ipxact")]
+            SourceType::IpXact => xml_names(text),
+            #[cfg(feature = "yaml")]
+            SourceType::Yaml => yaml_names(text),
+            #[cfg(feature = "json")]
+            SourceType::Json => json_names(text),
+            #[allow(unreachable_patterns)]
+            _ => Vec::new(),
+        };
+        Self {
+            file: file.to_string(),
+            text,
+            names,
+        }
+    }
+
+    /// Finds the item at `path`. Each segment is looked for among the descendants of the previous
+    /// one, the shallowest first so that an enumerated value doesn't pass for a field of the same
+    /// name. The deepest segment found is returned when the item itself has no `name` of its own,
+    /// like expanded array elements.
+    pub fn locate(&self, path: &str) -> Option<Location> {
+        let mut segments = path.split('.');
+        let first = segments.next()?;
+        // Peripherals are the shallowest items, don't mistake a register of the same name for one
+        let mut found = self
+            .names
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| matches(&n.value, first))
+            .min_by_key(|(_, n)| n.depth)?
+            .0;
+        for segment in segments {
+            let depth = self.names[found].depth;
+            match self.names[found + 1..]
+                .iter()
+                .enumerate()
+                .take_while(|(_, n)| n.depth > depth)
+                .filter(|(_, n)| matches(&n.value, segment))
+                .min_by_key(|(_, n)| n.depth)
+            {
+                Some((i, _)) => found += 1 + i,
+                None => break,
+            }
+        }
+        Some(self.location(&self.names[found]))
+    }
+
+    fn location(&self, name: &Name) -> Location {
+        let before = &self.text[..name.offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.text[name.offset..]
+            .find('\n')
+            .map_or(self.text.len(), |i| name.offset + i);
+        Location {
+            file: self.file.clone(),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            source_line: self.text[line_start..line_end].trim_end().to_string(),
+            len: name.value.chars().count(),
+        }
+    }
+}
+
+/// Whether the name written in the input is the one of a path segment, array names match their
+/// elements
+fn matches(name: &str, segment: &str) -> bool {
+    if name == segment {
+        return true;
+    }
+    let name = name.replace("[%s]", "%s");
+    match name.split_once("%s") {
+        Some((prefix, suffix)) => {
+            segment.len() > prefix.len() + suffix.len()
+                && segment.starts_with(prefix)
+                && segment.ends_with(suffix)
+        }
+        None => false,
+    }
+}
+
+/// `<name>` elements, also namespaced like `<ipxact:name>`, and `name` attributes as in ATDF
+fn xml_names(text: &str) -> Vec<Name> {
+    let mut names = Vec::new();
+    let mut depth = 0usize;
+    let mut pos = 0;
+    while let Some(start) = text[pos..].find('<').map(|i| pos + i) {
+        let rest = &text[start..];
+        if rest.starts_with("<!--") {
+            pos = rest.find("-->").map_or(text.len(), |i| start + i + 3);
+            continue;
+        }
+        let end = rest.find('>').map_or(text.len(), |i| start + i);
+        let tag = &text[start + 1..end];
+        pos = end;
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+        if tag.starts_with('/') {
+            depth = depth.saturating_sub(1);
+            continue;
+        }
+        let tag_name = tag.split(|c: char| c.is_whitespace() || c == '/').next();
+        let local = tag_name.map(|t| t.rsplit(':').next().unwrap_or(t));
+        if local == Some("name") && !tag.ends_with('/') {
+            // Unterminated tag at the end of the file
+            let value = match text.get(end + 1..) {
+                Some(value) => value,
+                None => break,
+            };
+            let value = &value[..value.find('<').unwrap_or(value.len())];
+            let trimmed = value.trim_start();
+            names.push(Name {
+                value: trimmed.trim_end().to_string(),
+                offset: end + 1 + value.len() - trimmed.len(),
+                depth,
+            });
+        } else if let Some(i) = tag.find(" name=\"") {
+            let offset = start + 1 + i + 7;
+            let value = &text[offset..];
+            names.push(Name {
+                value: value[..value.find('"').unwrap_or(0)].to_string(),
+                offset,
+                depth,
+            });
+        }
+        if !tag.ends_with('/') {
+            depth += 1;
+        }
+    }
+    names
+}
+
+/// `name:` keys, the depth is their indentation
+#[cfg(feature = "yaml")]
+fn yaml_names(text: &str) -> Vec<Name> {
+    let mut names = Vec::new();
+    let mut offset = 0;
+    for line in text.split('\n') {
+        let key = line.trim_start().trim_start_matches("- ");
+        if let Some(value) = key.strip_prefix("name:") {
+            let depth = line.len() - key.len();
+            let start = value.len() - value.trim_start().len();
+            let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+            names.push(Name {
+                value: value.to_string(),
+                offset: offset + depth + 5 + start,
+                depth,
+            });
+        }
+        offset += line.len() + 1;
+    }
+    names
+}
+
+/// `"name"` members, the depth is the number of enclosing objects and arrays
+#[cfg(feature = "json")]
+fn json_names(text: &str) -> Vec<Name> {
+    let mut names = Vec::new();
+    let mut depth = 0usize;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth = depth.saturating_sub(1),
+            '"' => {
+                let start = i + 1;
+                let mut end = None;
+                while let Some((j, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => {
+                            end = Some(j);
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                // Unterminated string at the end of the file
+                let end = match end {
+                    Some(end) => end,
+                    None => break,
+                };
+                let rest = text[end + 1..].trim_start();
+                if &text[start..end] == "name" && rest.starts_with(':') {
+                    let value = rest[1..].trim_start();
+                    if let Some(value) = value.strip_prefix('"') {
+                        let offset = text.len() - value.len();
+                        names.push(Name {
+                            value: value[..value.find('"').unwrap_or(0)].to_string(),
+                            offset,
+                            depth,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = "<device>
+  <peripheral>
+    <name>TIM1</name>
+    <register>
+      <name>CR1</name>
+      <field>
+        <name>MODE</name>
+        <enumeratedValue><name>EN</name></enumeratedValue>
+      </field>
+      <field>
+        <name>EN</name>
+      </field>
+    </register>
+  </peripheral>
+</device>";
+
+    #[test]
+    fn nested_path() {
+        let map = SourceMap::new("a.svd", XML, SourceType::Xml);
+        let location = map.locate("TIM1.CR1.EN").unwrap();
+        assert_eq!((location.line, location.column), (11, 15));
+        let location = map.locate("TIM1.CR1.NONE").unwrap();
+        assert_eq!((location.line, location.column), (5, 13));
+        assert!(map.locate("TIM2").is_none());
+    }
+
+    #[test]
+    fn truncated_input() {
+        let map = SourceMap::new("a.svd", "<device><name", SourceType::Xml);
+        assert!(map.locate("A").is_none());
+        #[cfg(feature = "json")]
+        for text in ["{\"name\": \"A\", \"", "{\"name\": \"A", "\""] {
+            SourceMap::new("a.json", text, SourceType::Json).locate("A");
+        }
+    }
+}
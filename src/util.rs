@@ -51,6 +51,16 @@ pub struct Config {
     pub feature_peripheral: bool,
     #[cfg_attr(feature = "serde", serde(default))]
     pub max_cluster_size: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub index_enums: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub peripheral_arrays: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fail_fast: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub deny_warnings: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub deny: Option<String>,
     #[cfg_attr(feature = "serde", serde(default = "current_dir"))]
     pub output_dir: PathBuf,
     #[cfg_attr(feature = "serde", serde(default))]
@@ -81,6 +91,11 @@ impl Default for Config {
             feature_group: false,
             feature_peripheral: false,
             max_cluster_size: false,
+            index_enums: false,
+            peripheral_arrays: false,
+            fail_fast: false,
+            deny_warnings: false,
+            deny: None,
             output_dir: current_dir(),
             input: None,
             source_type: SourceType::default(),
@@ -104,6 +119,8 @@ pub enum Target {
     XtensaLX,
     #[cfg_attr(feature = "serde", serde(rename = "mips"))]
     Mips,
+    #[cfg_attr(feature = "serde", serde(rename = "avr"))]
+    Avr,
     #[cfg_attr(feature = "serde", serde(rename = "none"))]
     None,
 }
@@ -116,6 +133,7 @@ impl Target {
             "riscv" => Target::RISCV,
             "xtensa-lx" => Target::XtensaLX,
             "mips" => Target::Mips,
+            "avr" => Target::Avr,
             "none" => Target::None,
             _ => bail!("unknown target {}", s),
         })
@@ -140,6 +158,12 @@ pub enum SourceType {
     Yaml,
     #[cfg(feature = "json")]
     Json,
+    #[cfg(feature = "atdf")]
+    Atdf,
+    #[cfg(feature = "ipxact")]
+    IpXact,
+    #[cfg(feature = "systemrdl")]
+    SystemRdl,
 }
 
 impl Default for SourceType {
@@ -157,6 +181,12 @@ impl SourceType {
             "yml" | "yaml" => Some(Self::Yaml),
             #[cfg(feature = "json")]
             "json" => Some(Self::Json),
+            #[cfg(feature = "atdf")]
+            "atdf" => Some(Self::Atdf),
+            #[cfg(feature = "ipxact")]
+            "ipxact" => Some(Self::IpXact),
+            #[cfg(feature = "systemrdl")]
+            "rdl" => Some(Self::SystemRdl),
             _ => None,
         }
     }
@@ -173,26 +203,26 @@ pub trait ToSanitizedCase {
     /// Convert self into PascalCase.
     ///
     /// Use on name of enumeration values.
-    fn to_sanitized_pascal_case(&self) -> Cow<str>;
+    fn to_sanitized_pascal_case(&self) -> Cow<'_, str>;
     fn to_pascal_case_ident(&self, span: Span) -> Ident {
         Ident::new(&self.to_sanitized_pascal_case(), span)
     }
     /// Convert self into CONSTANT_CASE.
     ///
     /// Use on name of reader structs, writer structs and enumerations.
-    fn to_sanitized_constant_case(&self) -> Cow<str>;
+    fn to_sanitized_constant_case(&self) -> Cow<'_, str>;
     fn to_constant_case_ident(&self, span: Span) -> Ident {
         Ident::new(&self.to_sanitized_constant_case(), span)
     }
     /// Convert self into snake_case, must use only if the target is used with extra prefix or suffix.
-    fn to_sanitized_not_keyword_snake_case(&self) -> Cow<str>; // snake_case
+    fn to_sanitized_not_keyword_snake_case(&self) -> Cow<'_, str>; // snake_case
     /// Convert self into snake_case target and ensure target is not a Rust keyword.
     ///
     /// If the sanitized target is a Rust keyword, this function adds an underline `_`
     /// to it.
     ///
     /// Use on name of peripheral modules, register modules and field modules.
-    fn to_sanitized_snake_case(&self) -> Cow<str> {
+    fn to_sanitized_snake_case(&self) -> Cow<'_, str> {
         let s = self.to_sanitized_not_keyword_snake_case();
         sanitize_keyword(s)
     }
@@ -202,7 +232,7 @@ pub trait ToSanitizedCase {
 }
 
 impl ToSanitizedCase for str {
-    fn to_sanitized_pascal_case(&self) -> Cow<str> {
+    fn to_sanitized_pascal_case(&self) -> Cow<'_, str> {
         let s = self.replace(BLACKLIST_CHARS, "");
 
         match s.chars().next().unwrap_or('\0') {
@@ -212,7 +242,7 @@ impl ToSanitizedCase for str {
             _ => Cow::from(s.to_pascal_case()),
         }
     }
-    fn to_sanitized_constant_case(&self) -> Cow<str> {
+    fn to_sanitized_constant_case(&self) -> Cow<'_, str> {
         let s = self.replace(BLACKLIST_CHARS, "");
 
         match s.chars().next().unwrap_or('\0') {
@@ -222,7 +252,7 @@ impl ToSanitizedCase for str {
             _ => Cow::from(s.to_constant_case()),
         }
     }
-    fn to_sanitized_not_keyword_snake_case(&self) -> Cow<str> {
+    fn to_sanitized_not_keyword_snake_case(&self) -> Cow<'_, str> {
         const INTERNALS: [&str; 4] = ["set_bit", "clear_bit", "bit", "bits"];
 
         let s = self.replace(BLACKLIST_CHARS, "");
@@ -288,7 +318,7 @@ pub fn escape_brackets(s: &str) -> String {
         })
 }
 
-pub fn name_of<T: FullName>(maybe_array: &MaybeArray<T>, ignore_group: bool) -> Cow<str> {
+pub fn name_of<T: FullName>(maybe_array: &MaybeArray<T>, ignore_group: bool) -> Cow<'_, str> {
     match maybe_array {
         MaybeArray::Single(info) => info.fullname(ignore_group),
         MaybeArray::Array(info, _) => replace_suffix(&info.fullname(ignore_group), "").into(),
@@ -520,6 +550,46 @@ impl U32Ext for u32 {
     }
 }
 
+/// Renders each of `items` to code, across threads with the `parallel` feature, returning the
+/// results in the order of `items`. `render` also returns side data, like warnings, which must be
+/// `Send`. With `fail_fast` rendering stops at the first error, which is returned.
+pub fn render_each<T, W, F>(
+    items: &mut [T],
+    config: &Config,
+    render: F,
+) -> Result<Vec<(Result<String>, W)>>
+where
+    T: Send,
+    W: Send,
+    F: Fn(&mut T) -> (Result<TokenStream>, W) + Sync + Send,
+{
+    // `TokenStream` can't cross threads, pass the code as text
+    let render = |item: &mut T| {
+        let (tokens, data) = render(item);
+        (tokens.map(|t| t.to_string()), data)
+    };
+    let fail_fast = |(code, data): (Result<String>, W)| code.map(|code| (Ok(code), data));
+
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+
+        if config.fail_fast {
+            items.par_iter_mut().map(render).map(fail_fast).collect()
+        } else {
+            Ok(items.par_iter_mut().map(render).collect())
+        }
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        if config.fail_fast {
+            items.iter_mut().map(render).map(fail_fast).collect()
+        } else {
+            Ok(items.iter_mut().map(render).collect())
+        }
+    }
+}
+
 pub fn build_rs() -> TokenStream {
     quote! {
         use std::env;
@@ -560,11 +630,11 @@ pub fn get_register_sizes(d: &Device) -> Vec<u32> {
 }
 
 pub trait FullName {
-    fn fullname(&self, ignore_group: bool) -> Cow<str>;
+    fn fullname(&self, ignore_group: bool) -> Cow<'_, str>;
 }
 
 impl FullName for RegisterInfo {
-    fn fullname(&self, ignore_group: bool) -> Cow<str> {
+    fn fullname(&self, ignore_group: bool) -> Cow<'_, str> {
         match &self.alternate_group {
             Some(group) if !ignore_group => format!("{group}_{}", self.name).into(),
             _ => self.name.as_str().into(),
@@ -573,12 +643,12 @@ impl FullName for RegisterInfo {
 }
 
 impl FullName for PeripheralInfo {
-    fn fullname(&self, _ignore_group: bool) -> Cow<str> {
+    fn fullname(&self, _ignore_group: bool) -> Cow<'_, str> {
         self.name.as_str().into()
     }
 }
 
-pub fn group_names(d: &Device) -> Vec<Cow<str>> {
+pub fn group_names(d: &Device) -> Vec<Cow<'_, str>> {
     let set: HashSet<_> = d
         .peripherals
         .iter()